@@ -0,0 +1,113 @@
+use crate::assets::*;
+use crate::data::{Session, SessionType};
+use chrono::Duration;
+
+/// Work sessions longer than this count as deep focus...
+const FOCUS_AFTER: Duration = Duration::minutes(25);
+/// ...and longer than this as overwork.
+const OVERWORK_AFTER: Duration = Duration::minutes(90);
+
+const FRAMES_IDLE: &[&[&str]] = &[&FRAME_DEAD];
+
+/// Which set of frames the pet plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Animation {
+    Idle,
+    Working,
+    Focus,
+    Overwork,
+    Break,
+    Celebrate,
+}
+
+impl Animation {
+    pub const ALL: [Animation; 6] = [
+        Animation::Idle,
+        Animation::Working,
+        Animation::Focus,
+        Animation::Overwork,
+        Animation::Break,
+        Animation::Celebrate,
+    ];
+
+    /// Name of the frame set in a skin file.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Animation::Idle => "idle",
+            Animation::Working => "work",
+            Animation::Focus => "focus",
+            Animation::Overwork => "overwork",
+            Animation::Break => "break",
+            Animation::Celebrate => "celebrate",
+        }
+    }
+
+    /// Picks the animation for the running session. Work moves from
+    /// working to focus to overwork as the session gets longer; a reached
+    /// goal interrupts anything but idling.
+    pub fn select(session: &Session, celebrating: bool) -> Self {
+        match session.session_type {
+            SessionType::Idle => Animation::Idle,
+            _ if celebrating => Animation::Celebrate,
+            SessionType::Break => Animation::Break,
+            SessionType::Work => {
+                let elapsed = session.duration();
+                if elapsed > OVERWORK_AFTER {
+                    Animation::Overwork
+                } else if elapsed > FOCUS_AFTER {
+                    Animation::Focus
+                } else {
+                    Animation::Working
+                }
+            }
+        }
+    }
+
+    /// The built-in frames.
+    pub fn frames(&self) -> &'static [&'static [&'static str]] {
+        match self {
+            Animation::Idle => FRAMES_IDLE,
+            Animation::Working => FRAMES_ACTIVE,
+            Animation::Focus => FRAMES_FOCUS,
+            Animation::Overwork => FRAMES_OVERWORK,
+            Animation::Break => FRAMES_BREAK,
+            Animation::Celebrate => FRAMES_CELEBRATE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::utc;
+
+    /// A session of `kind` that has lasted `length`.
+    fn lasted(kind: SessionType, length: Duration) -> Session {
+        let start = utc("2024-05-06T09:00:00Z");
+        let mut session = Session::new(kind, start);
+        session.end_time = Some(start + length);
+        session
+    }
+
+    #[test]
+    fn work_turns_into_focus_and_overwork_past_the_thresholds() {
+        let work = |length| Animation::select(&lasted(SessionType::Work, length), false);
+        let second = Duration::seconds(1);
+        assert_eq!(work(Duration::zero()), Animation::Working);
+        assert_eq!(work(FOCUS_AFTER), Animation::Working);
+        assert_eq!(work(FOCUS_AFTER + second), Animation::Focus);
+        assert_eq!(work(OVERWORK_AFTER), Animation::Focus);
+        assert_eq!(work(OVERWORK_AFTER + second), Animation::Overwork);
+    }
+
+    #[test]
+    fn breaks_stay_breaks_and_goals_are_celebrated_unless_idle() {
+        let long = Duration::hours(3);
+        let select = |kind, celebrating| Animation::select(&lasted(kind, long), celebrating);
+        assert_eq!(select(SessionType::Break, false), Animation::Break);
+        assert_eq!(select(SessionType::Idle, false), Animation::Idle);
+        assert_eq!(select(SessionType::Work, true), Animation::Celebrate);
+        assert_eq!(select(SessionType::Break, true), Animation::Celebrate);
+        assert_eq!(select(SessionType::Idle, true), Animation::Idle);
+    }
+}
@@ -253,3 +253,256 @@ pub const FRAMES_ACTIVE: &[&[&str]] = &[
         "--------------------                    --------------------",
     ],
 ];
+
+/// Columns of every frame between the two computers, where the pet lives.
+pub const PET_COLUMNS: std::ops::Range<usize> = 19..41;
+
+/// Lines of the frames replaced at each evolution stage. The full-grown
+/// heart is the art above; the crowned heart only adds a crown to it.
+pub const STAGE_EGG: &[(usize, &str)] = &[
+    (1, "                      "),
+    (2, "       .--.           "),
+    (3, "      /    \\          "),
+    (4, "     |  ~~  |         "),
+    (5, "     |      |         "),
+    (6, "      \\    /          "),
+    (7, "       '--'           "),
+];
+
+pub const STAGE_SPARK: &[(usize, &str)] = &[
+    (1, "                      "),
+    (2, "                      "),
+    (3, "      .-. .-.         "),
+    (4, "     (   '   )        "),
+    (5, "      '.   .'         "),
+    (6, "        '.'           "),
+    (7, "         .            "),
+];
+
+pub const STAGE_CROWNED: &[(usize, &str)] = &[(0, "   *  |\\/\\/\\/\\|  *    ")];
+
+/// On a break: eyes closed, dozing.
+pub const FRAMES_BREAK: &[&[&str]] = &[
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |        z  | |    .****. .****.     | |        z  | |",
+        "  | |   -   -   | |    .*****.*****.     | |   -   -   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |    ___    | |      .*******.       | |    ___    | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |       z Z | |    .****. .****.     | |       z Z | |",
+        "  | |   -   -   | |    .*****.*****.     | |   -   -   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |    ___    | |      .*******.       | |    ___    | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |      z Z z| |    .****. .****.     | |      z Z z| |",
+        "  | |   -   -   | |    .*****.*****.     | |   -   -   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |    ___    | |      .*******.       | |    ___    | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |       Z z | |    .****. .****.     | |       Z z | |",
+        "  | |   -   -   | |    .*****.*****.     | |   -   -   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |    ___    | |      .*******.       | |    ___    | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+];
+
+/// Deep focus: a long work session going strong.
+pub const FRAMES_FOCUS: &[&[&str]] = &[
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |   _   _   | |    .****. .****.     | |   _   _   | |",
+        "  | |   O   O   | |    .*****.*****.     | |   O   O   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |    ---    | |      .*******.       | |    ---    | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_.^..........*..........^...._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________     ***     ***        |*\\_/*|________",
+        "  |  ___________  |    *^^^** **^^^*     ||_/-\\_|______  |",
+        "  | |   _   _   | |   *^****^*^****^*    | |   _   _   | |",
+        "  | |   O   O   | |   *^*****^*****^*    | |   O   O   | |",
+        "  | |     -     | |    *^*********^*     | |     -     | |",
+        "  | |    ---    | |     *^*******^*      | |    ---    | |",
+        "  | |___     ___| |      *^*****^*       | |___________| |",
+        "  |_____|\\_/|_____|       *^***^*        |_______________|",
+        "    _|__|/ \\|_|_....^.......*.......^......._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |   _   _   | |    .****. .****.     | |   _   _   | |",
+        "  | |   O   O   | |    .*****.*****.     | |   O   O   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |    ---    | |      .*******.       | |    ---    | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_.......^....*....^.........._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________     ***     ***        |*\\_/*|________",
+        "  |  ___________  |    *^^^** **^^^*     ||_/-\\_|______  |",
+        "  | |   _   _   | |   *^****^*^****^*    | |   _   _   | |",
+        "  | |   O   O   | |   *^*****^*****^*    | |   O   O   | |",
+        "  | |     -     | |    *^*********^*     | |     -     | |",
+        "  | |    ---    | |     *^*******^*      | |    ---    | |",
+        "  | |___     ___| |      *^*****^*       | |___________| |",
+        "  |_____|\\_/|_____|       *^***^*        |_______________|",
+        "    _|__|/ \\|_|_..........^.*.^............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+];
+
+/// Overwork: far too long without a break, tired and sweating.
+pub const FRAMES_OVERWORK: &[&[&str]] = &[
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | | '         | |    .****. .****.     | |         ' | |",
+        "  | |   =   =   | |    .*****.*****.     | |   =   =   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |   /---\\   | |      .*******.       | |   /---\\   | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |           | |    .****. .****.     | |           | |",
+        "  | | ' =   =   | |    .*****.*****.     | |   =   = ' | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |   /---\\   | |      .*******.       | |   /---\\   | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |           | |    .****. .****.     | |           | |",
+        "  | |   =   =   | |    .*****.*****.     | |   =   =   | |",
+        "  | | '   -     | |     .*********.      | |     -   ' | |",
+        "  | |   /---\\   | |      .*******.       | |   /---\\   | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________                        |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |         ' | |    .****. .****.     | | '         | |",
+        "  | |   =   =   | |    .*****.*****.     | |   =   =   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | | ' /---\\   | |      .*******.       | |   /---\\ ' | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\                          / ********** \\",
+        " /  ************  \\                      /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+];
+
+/// Celebrating a reached goal.
+pub const FRAMES_CELEBRATE: &[&[&str]] = &[
+    &[
+        "   _______________  *    +    .    *   +  |*\\_/*|________",
+        "  |  ___________  |    *^^^** **^^^*     ||_/-\\_|______  |",
+        "  | |           | |   *^****^*^****^*    | |           | |",
+        "  | |   ^   ^   | |   *^*****^*****^*    | |   ^   ^   | |",
+        "  | |     -     | |    *^*********^*     | |     -     | |",
+        "  | |   \\___/   | |     *^*******^*      | |   \\___/   | |",
+        "  | |___     ___| |      *^*****^*       | |___________| |",
+        "  |_____|\\_/|_____|       *^***^*        |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\     +   *    .   *       / ********** \\",
+        " /  ************  \\  . *   +   *   .     /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________  +    .    *    +   *  |*\\_/*|________",
+        "  |  ___________  |     .-.     .-.      ||_/-\\_|______  |",
+        "  | |           | |    .****. .****.     | |           | |",
+        "  | |   ^   ^   | |    .*****.*****.     | |   ^   ^   | |",
+        "  | |     -     | |     .*********.      | |     -     | |",
+        "  | |   \\___/   | |      .*******.       | |   \\___/   | |",
+        "  | |___     ___| |       .*****.        | |___________| |",
+        "  |_____|\\_/|_____|        .***.         |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\     *   .    +   .       / ********** \\",
+        " /  ************  \\  * +   .   +   *     /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+    &[
+        "   _______________  .    *    +    .   *  |*\\_/*|________",
+        "  |  ___________  |    *^^^** **^^^*     ||_/-\\_|______  |",
+        "  | |           | |   *^****^*^****^*    | |           | |",
+        "  | |   ^   ^   | |   *^*****^*****^*    | |   ^   ^   | |",
+        "  | |     -     | |    *^*********^*     | |     -     | |",
+        "  | |   \\___/   | |     *^*******^*      | |   \\___/   | |",
+        "  | |___     ___| |      *^*****^*       | |___________| |",
+        "  |_____|\\_/|_____|       *^***^*        |_______________|",
+        "    _|__|/ \\|_|_............*..............._|________|_",
+        "   / ********** \\     .   +    *   +       / ********** \\",
+        " /  ************  \\  + .   *   .   +     /  ************  \\",
+        "--------------------                    --------------------",
+    ],
+];
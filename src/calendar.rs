@@ -0,0 +1,305 @@
+use anyhow::{Result, anyhow};
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone,
+    Utc,
+};
+use chrono_tz::Tz;
+use std::sync::OnceLock;
+
+/// A timezone: the system's, an IANA zone or a fixed UTC offset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    Local,
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+impl Zone {
+    /// Parses `local`, an IANA name like `Europe/Berlin` (or `UTC`) or an
+    /// offset like `+05:30`.
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("local") {
+            return Ok(Zone::Local);
+        }
+        if let Ok(tz) = name.parse::<Tz>() {
+            return Ok(Zone::Named(tz));
+        }
+        name.parse::<FixedOffset>().map(Zone::Fixed).map_err(|_| {
+            anyhow!("unknown timezone {name:?}, expected an IANA name like Europe/Berlin or an offset like +02:00")
+        })
+    }
+
+    /// Name to record on a session: the IANA name when known, else the
+    /// current offset.
+    pub fn name(&self) -> String {
+        match self {
+            Zone::Named(tz) => tz.name().to_string(),
+            Zone::Fixed(offset) => offset.to_string(),
+            Zone::Local => iana_time_zone::get_timezone()
+                .ok()
+                .filter(|name| name.parse::<Tz>().is_ok())
+                .unwrap_or_else(|| Local::now().offset().to_string()),
+        }
+    }
+
+    pub fn offset_at(&self, t: DateTime<Utc>) -> FixedOffset {
+        let utc = t.naive_utc();
+        match self {
+            Zone::Local => Local.offset_from_utc_datetime(&utc).fix(),
+            Zone::Named(tz) => tz.offset_from_utc_datetime(&utc).fix(),
+            Zone::Fixed(offset) => *offset,
+        }
+    }
+
+    pub fn local_time(&self, t: DateTime<Utc>) -> DateTime<FixedOffset> {
+        t.with_timezone(&self.offset_at(t))
+    }
+
+    /// The instant a wall-clock time refers to. Times repeated when the
+    /// clocks go back take the earlier one; times skipped when they go
+    /// forward do not exist.
+    pub fn instant_at(&self, naive: &NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Zone::Local => Local
+                .from_local_datetime(naive)
+                .earliest()
+                .map(|t| t.to_utc()),
+            Zone::Named(tz) => tz.from_local_datetime(naive).earliest().map(|t| t.to_utc()),
+            Zone::Fixed(offset) => offset
+                .from_local_datetime(naive)
+                .earliest()
+                .map(|t| t.to_utc()),
+        }
+    }
+}
+
+/// Maps instants to the days they are reported under.
+#[derive(Debug, Clone, Copy)]
+pub struct Calendar {
+    pub zone: Zone,
+    /// Wall-clock time a day starts at; earlier hours belong to the day
+    /// before.
+    pub day_starts_at: NaiveTime,
+}
+
+impl Calendar {
+    pub fn new(zone: Zone) -> Self {
+        Calendar {
+            zone,
+            day_starts_at: NaiveTime::MIN,
+        }
+    }
+
+    pub fn with_day_start(self, day_starts_at: NaiveTime) -> Self {
+        Calendar {
+            day_starts_at,
+            ..self
+        }
+    }
+
+    /// Offset of the day start from midnight.
+    fn cutoff(&self) -> Duration {
+        self.day_starts_at - NaiveTime::MIN
+    }
+
+    /// First instant of `date`. A day whose start is skipped by a DST jump
+    /// starts once the clocks have moved on.
+    pub fn day_start(&self, date: NaiveDate) -> DateTime<Utc> {
+        let start = date.and_time(self.day_starts_at);
+        (0..=12)
+            .find_map(|quarter| {
+                self.zone
+                    .instant_at(&(start + Duration::minutes(15 * quarter)))
+            })
+            .unwrap_or_else(|| start.and_utc())
+    }
+
+    /// Start and end of `date`; 23 or 25 hours apart on DST changes.
+    pub fn day_bounds(&self, date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+        (
+            self.day_start(date),
+            self.day_start(date + Duration::days(1)),
+        )
+    }
+
+    /// The day `t` counts towards.
+    pub fn date_of(&self, t: DateTime<Utc>) -> NaiveDate {
+        (self.zone.local_time(t).naive_local() - self.cutoff()).date()
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.date_of(Utc::now())
+    }
+}
+
+static CALENDAR: OnceLock<Calendar> = OnceLock::new();
+
+/// Sets the calendar every report uses. Only the first call counts.
+pub fn set_calendar(calendar: Calendar) {
+    CALENDAR.get_or_init(|| calendar);
+}
+
+/// The reporting calendar, the system timezone unless configured.
+pub fn calendar() -> Calendar {
+    *CALENDAR.get_or_init(|| Calendar::new(Zone::Local))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Session, SessionType};
+    use crate::testing::{date, session, utc};
+
+    fn berlin() -> Calendar {
+        Calendar::new(Zone::parse("Europe/Berlin").unwrap())
+    }
+
+    fn work(start: &str, end: &str, zone: &str) -> Session {
+        Session {
+            zone: Some(zone.to_string()),
+            ..session(SessionType::Work, start, Some(end))
+        }
+    }
+
+    #[test]
+    fn parses_zones() {
+        assert_eq!(Zone::parse("local").unwrap(), Zone::Local);
+        assert_eq!(Zone::parse("UTC").unwrap().name(), "UTC");
+        assert_eq!(
+            Zone::parse("America/New_York").unwrap().name(),
+            "America/New_York"
+        );
+        assert_eq!(Zone::parse("+05:30").unwrap().name(), "+05:30");
+        assert!(Zone::parse("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn spring_forward_day_has_23_hours() {
+        let (from, to) = berlin().day_bounds(date("2026-03-29"));
+        assert_eq!(from, utc("2026-03-28T23:00:00Z"));
+        assert_eq!(to - from, Duration::hours(23));
+    }
+
+    #[test]
+    fn fall_back_day_has_25_hours() {
+        let (from, to) = berlin().day_bounds(date("2026-10-25"));
+        assert_eq!(from, utc("2026-10-24T22:00:00Z"));
+        assert_eq!(to - from, Duration::hours(25));
+    }
+
+    #[test]
+    fn ordinary_days_have_24_hours() {
+        let calendar = berlin();
+        for day in ["2026-01-15", "2026-03-28", "2026-03-30", "2026-10-26"] {
+            let (from, to) = calendar.day_bounds(date(day));
+            assert_eq!(to - from, Duration::hours(24), "{day}");
+        }
+    }
+
+    #[test]
+    fn skipped_midnight_starts_the_day_after_the_jump() {
+        // Santiago moved its clocks from 00:00 to 01:00 on 2022-09-11
+        let santiago = Calendar::new(Zone::parse("America/Santiago").unwrap());
+        let (from, to) = santiago.day_bounds(date("2022-09-11"));
+        assert_eq!(from, utc("2022-09-11T04:00:00Z"));
+        assert_eq!(to - from, Duration::hours(23));
+    }
+
+    #[test]
+    fn session_across_spring_forward_is_split_by_wall_clock_days() {
+        // 22:00 on Mar 28 to 04:00 on Mar 29 Berlin time: 2h before
+        // midnight, 3h after since 02:00-03:00 does not exist
+        let calendar = berlin();
+        let session = work(
+            "2026-03-28T21:00:00Z",
+            "2026-03-29T02:00:00Z",
+            "Europe/Berlin",
+        );
+        let (from, to) = calendar.day_bounds(date("2026-03-28"));
+        assert_eq!(session.duration_between(from, to), Duration::hours(2));
+        let (from, to) = calendar.day_bounds(date("2026-03-29"));
+        assert_eq!(session.duration_between(from, to), Duration::hours(3));
+    }
+
+    #[test]
+    fn repeated_hour_counts_twice_on_fall_back() {
+        // 01:00 to 04:00 Berlin on Oct 25 spans four real hours
+        let calendar = berlin();
+        let session = work(
+            "2026-10-24T23:00:00Z",
+            "2026-10-25T03:00:00Z",
+            "Europe/Berlin",
+        );
+        let (from, to) = calendar.day_bounds(date("2026-10-25"));
+        assert_eq!(session.duration_between(from, to), Duration::hours(4));
+        let local = NaiveDate::from_ymd_opt(2026, 10, 25)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+        assert_eq!(
+            calendar.zone.instant_at(&local),
+            Some(utc("2026-10-25T00:30:00Z"))
+        );
+    }
+
+    #[test]
+    fn sessions_recorded_elsewhere_are_bucketed_in_the_reporting_zone() {
+        // Late evening in New York is the next morning in Berlin
+        let session = work(
+            "2026-06-02T03:30:00Z",
+            "2026-06-02T05:00:00Z",
+            "America/New_York",
+        );
+        let new_york = Calendar::new(Zone::parse(session.zone.as_deref().unwrap()).unwrap());
+        assert_eq!(new_york.date_of(session.start_time), date("2026-06-01"));
+        assert_eq!(berlin().date_of(session.start_time), date("2026-06-02"));
+
+        let (from, to) = berlin().day_bounds(date("2026-06-02"));
+        assert_eq!(session.duration_between(from, to), Duration::minutes(90));
+        let (from, to) = berlin().day_bounds(date("2026-06-01"));
+        assert_eq!(session.duration_between(from, to), Duration::zero());
+    }
+
+    #[test]
+    fn hours_before_the_day_start_belong_to_the_day_before() {
+        let calendar = Calendar::new(Zone::parse("UTC").unwrap())
+            .with_day_start(NaiveTime::from_hms_opt(4, 0, 0).unwrap());
+        assert_eq!(
+            calendar.date_of(utc("2026-05-06T03:59:00Z")),
+            date("2026-05-05")
+        );
+        assert_eq!(
+            calendar.date_of(utc("2026-05-06T04:00:00Z")),
+            date("2026-05-06")
+        );
+
+        let (from, to) = calendar.day_bounds(date("2026-05-05"));
+        assert_eq!(from, utc("2026-05-05T04:00:00Z"));
+        assert_eq!(to, utc("2026-05-06T04:00:00Z"));
+        // Working from 22:00 to 03:00 all counts towards the 5th
+        let session = work("2026-05-05T22:00:00Z", "2026-05-06T03:00:00Z", "UTC");
+        assert_eq!(session.duration_between(from, to), Duration::hours(5));
+    }
+
+    #[test]
+    fn day_start_follows_dst() {
+        // Days run from 04:00 to 04:00 Berlin time, whatever the offset
+        let calendar = berlin().with_day_start(NaiveTime::from_hms_opt(4, 0, 0).unwrap());
+        let (from, to) = calendar.day_bounds(date("2026-03-28"));
+        assert_eq!(from, utc("2026-03-28T03:00:00Z"));
+        assert_eq!(to, utc("2026-03-29T02:00:00Z"));
+        assert_eq!(
+            calendar.date_of(utc("2026-03-29T01:30:00Z")),
+            date("2026-03-28")
+        );
+    }
+
+    #[test]
+    fn fixed_offsets_ignore_dst() {
+        let calendar = Calendar::new(Zone::parse("+01:00").unwrap());
+        let (from, to) = calendar.day_bounds(date("2026-03-29"));
+        assert_eq!(from, utc("2026-03-28T23:00:00Z"));
+        assert_eq!(to - from, Duration::hours(24));
+    }
+}
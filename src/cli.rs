@@ -0,0 +1,487 @@
+use crate::App;
+use crate::calendar::calendar;
+use crate::config::Config;
+use crate::data::{Session, SessionType, Store, local_day_bounds};
+use crate::editor::LogOptions;
+use crate::export::{ExportFormat, ExportOptions};
+use crate::import::{ImportOptions, NonBillable};
+use crate::ui::format_duration_str;
+use anyhow::{Context, Result, bail};
+use chrono::{Duration, NaiveDate};
+use serde_json::json;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: rust_pet_timer [--data-file PATH] [--config PATH] [COMMAND]
+
+Without a command the full-screen timer starts.
+
+Commands:
+  start [OPTIONS]             Start a work session
+      --note TEXT               Note for the session
+      --project NAME            Project the session belongs to
+      --tag TAG                 Tag the session (repeatable)
+  break                       Start a break
+  stop                        Stop tracking (idle)
+  toggle                      Switch between work and break
+  note TEXT                   Set the note of the running session
+  status [--format text|json] Show the running session and today's totals
+  export csv|ics [OPTIONS]    Export sessions as CSV or iCalendar
+      --from YYYY-MM-DD         First day to include (default: all history)
+      --to YYYY-MM-DD           Last day to include (default: today)
+      --tz local|utc            Timezone of CSV times (default: local, the
+                                reporting timezone)
+      --include-idle            Also export idle sessions to CSV
+      -o, --output FILE         Write to FILE instead of stdout
+  import FILE [OPTIONS]       Import a Toggl Track or Clockify detailed report (CSV)
+      --apply                   Save the new sessions (default: only show a summary)
+      --tz local|utc            Timezone of the times in the report (default: local,
+                                the reporting timezone)
+      --non-billable work|break|skip
+                                What non-billable rows become (default: work)
+      --date-format FMT         Date format of the report, e.g. %d/%m/%Y
+  log START END [OPTIONS]     Add a finished session, times as HH:MM
+      --date YYYY-MM-DD         Day of the session (default: today)
+      --type work|break         Kind of session (default: work)
+      --note TEXT               Note for the session
+      --project NAME            Project the session belongs to
+      --tag TAG                 Tag the session (repeatable)
+  validate-skin NAME|FILE     Check that a pet skin parses and fits the pet panel
+  daemon                      Own the log and serve other instances over a socket
+
+While a daemon is running, the TUI and the commands above talk to it instead
+of opening the data file themselves.";
+
+/// What to do once the data file is resolved.
+#[derive(Debug, Default)]
+pub enum Command {
+    #[default]
+    Tui,
+    Start {
+        note: Option<String>,
+        project: Option<String>,
+        tags: Vec<String>,
+    },
+    Break,
+    Stop,
+    Toggle,
+    Note {
+        text: String,
+    },
+    Status {
+        json: bool,
+    },
+    Export(ExportOptions),
+    Import(ImportOptions),
+    Log(LogOptions),
+    ValidateSkin {
+        skin: String,
+    },
+    Daemon,
+}
+
+/// Flags that can be given on the command line.
+#[derive(Debug, Default)]
+pub struct CliArgs {
+    pub data_file: Option<PathBuf>,
+    pub config_file: Option<PathBuf>,
+    pub command: Command,
+}
+
+impl CliArgs {
+    /// Global flags go before the command; everything from the command
+    /// name on is the command's own, so a note may say `help`.
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self> {
+        let mut cli = CliArgs::default();
+        let mut rest = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--data-file" => cli.data_file = Some(next_value(&mut args, &arg)?.into()),
+                "--config" => cli.config_file = Some(next_value(&mut args, &arg)?.into()),
+                "-h" | "--help" | "help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => {
+                    rest.push(arg);
+                    rest.extend(args);
+                    break;
+                }
+            }
+        }
+        cli.command = parse_command(rest)?;
+        Ok(cli)
+    }
+}
+
+fn next_value<I: Iterator<Item = String>>(args: &mut I, flag: &str) -> Result<String> {
+    args.next()
+        .with_context(|| format!("{flag} expects a value"))
+}
+
+fn parse_command(args: Vec<String>) -> Result<Command> {
+    let mut args = args.into_iter();
+    let Some(name) = args.next() else {
+        return Ok(Command::Tui);
+    };
+    let command = match name.as_str() {
+        "start" => {
+            let mut note = None;
+            let mut project = None;
+            let mut tags = Vec::new();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--note" => note = Some(next_value(&mut args, &arg)?),
+                    "--project" => project = Some(next_value(&mut args, &arg)?),
+                    "--tag" => tags.push(next_value(&mut args, &arg)?),
+                    other => bail!("start: unexpected argument {other}"),
+                }
+            }
+            Command::Start {
+                note,
+                project,
+                tags,
+            }
+        }
+        "break" => Command::Break,
+        "stop" => Command::Stop,
+        "toggle" => Command::Toggle,
+        "daemon" => Command::Daemon,
+        "note" => {
+            let text = args.by_ref().collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                bail!("note: missing text");
+            }
+            Command::Note { text }
+        }
+        "status" => {
+            let mut json = false;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--format" => match next_value(&mut args, &arg)?.as_str() {
+                        "json" => json = true,
+                        "text" => json = false,
+                        other => bail!("status: unknown format {other}"),
+                    },
+                    other => bail!("status: unexpected argument {other}"),
+                }
+            }
+            Command::Status { json }
+        }
+        "export" => Command::Export(parse_export(&mut args)?),
+        "import" => Command::Import(parse_import(&mut args)?),
+        "log" => Command::Log(parse_log(&mut args)?),
+        "validate-skin" => match (args.next(), args.next()) {
+            (Some(skin), None) => Command::ValidateSkin { skin },
+            (None, _) => bail!("validate-skin: missing skin name or file"),
+            (Some(_), Some(other)) => bail!("validate-skin: unexpected argument {other}"),
+        },
+        other => bail!("unknown command: {other}\n\n{USAGE}"),
+    };
+    if let Some(extra) = args.next() {
+        bail!("{name}: unexpected argument {extra}");
+    }
+    Ok(command)
+}
+
+fn parse_export(args: &mut impl Iterator<Item = String>) -> Result<ExportOptions> {
+    let format = match args.next().as_deref() {
+        Some("csv") => ExportFormat::Csv,
+        Some("ics") => ExportFormat::Ics,
+        Some(other) => bail!("export: unknown format {other}"),
+        None => bail!("export: missing format"),
+    };
+    let mut options = ExportOptions {
+        format,
+        from: None,
+        to: None,
+        utc: false,
+        include_idle: false,
+        output: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => options.from = Some(parse_date(&next_value(args, &arg)?)?),
+            "--to" => options.to = Some(parse_date(&next_value(args, &arg)?)?),
+            "--tz" => {
+                options.utc = match next_value(args, &arg)?.as_str() {
+                    "utc" => true,
+                    "local" => false,
+                    other => bail!("export: unknown timezone {other}"),
+                }
+            }
+            "--include-idle" => options.include_idle = true,
+            "-o" | "--output" => options.output = Some(next_value(args, &arg)?.into()),
+            other => bail!("export: unexpected argument {other}"),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_import(args: &mut impl Iterator<Item = String>) -> Result<ImportOptions> {
+    let mut path = None;
+    let mut options = ImportOptions {
+        path: PathBuf::new(),
+        utc: false,
+        non_billable: NonBillable::Work,
+        date_format: None,
+        apply: false,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--apply" => options.apply = true,
+            "--tz" => {
+                options.utc = match next_value(args, &arg)?.as_str() {
+                    "utc" => true,
+                    "local" => false,
+                    other => bail!("import: unknown timezone {other}"),
+                }
+            }
+            "--non-billable" => {
+                options.non_billable = match next_value(args, &arg)?.as_str() {
+                    "work" => NonBillable::Work,
+                    "break" => NonBillable::Break,
+                    "skip" => NonBillable::Skip,
+                    other => {
+                        bail!("import: --non-billable expects work, break or skip, not {other}")
+                    }
+                }
+            }
+            "--date-format" => options.date_format = Some(next_value(args, &arg)?),
+            other if other.starts_with('-') => bail!("import: unexpected argument {other}"),
+            _ if path.is_none() => path = Some(arg.into()),
+            other => bail!("import: unexpected argument {other}"),
+        }
+    }
+    options.path = path.context("import: missing file")?;
+    Ok(options)
+}
+
+fn parse_log(args: &mut impl Iterator<Item = String>) -> Result<LogOptions> {
+    let mut times = Vec::new();
+    let mut options = LogOptions {
+        date: None,
+        start: String::new(),
+        end: String::new(),
+        kind: SessionType::Work,
+        note: None,
+        project: None,
+        tags: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--date" => options.date = Some(parse_date(&next_value(args, &arg)?)?),
+            "--type" => {
+                options.kind = match next_value(args, &arg)?.as_str() {
+                    "work" => SessionType::Work,
+                    "break" => SessionType::Break,
+                    other => bail!("log: --type expects work or break, not {other}"),
+                }
+            }
+            "--note" => options.note = Some(next_value(args, &arg)?),
+            "--project" => options.project = Some(next_value(args, &arg)?),
+            "--tag" => options.tags.push(next_value(args, &arg)?),
+            other if other.starts_with('-') => bail!("log: unexpected argument {other}"),
+            _ if times.len() < 2 => times.push(arg),
+            other => bail!("log: unexpected argument {other}"),
+        }
+    }
+    let [start, end] = <[String; 2]>::try_from(times)
+        .map_err(|_| anyhow::anyhow!("log: expects a start and an end time"))?;
+    options.start = start;
+    options.end = end;
+    Ok(options)
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .with_context(|| format!("invalid date {value}, expected YYYY-MM-DD"))
+}
+
+/// Applies a state-changing command and prints the resulting session.
+pub fn run(config: &Config, store: Box<dyn Store>, command: Command) -> Result<()> {
+    // On a log with nothing running the session the command starts is
+    // opened right away, not after an idle placeholder
+    let (mut app, opened) = App::open(config, store, first_session(&command))?;
+    // ...which already was the toggle
+    if !(opened && matches!(command, Command::Toggle)) {
+        apply_command(&mut app, command);
+    }
+    app.flush()?;
+    println!("{}", describe(app.get_active_session()));
+    Ok(())
+}
+
+/// The session `command` starts when none is running.
+fn first_session(command: &Command) -> SessionType {
+    match command {
+        Command::Start { .. } | Command::Toggle => SessionType::Work,
+        Command::Break => SessionType::Break,
+        _ => SessionType::Idle,
+    }
+}
+
+/// Runs a command through the same transitions the TUI uses.
+pub fn apply_command(app: &mut App, command: Command) {
+    let current = app.get_active_session().session_type;
+    match command {
+        Command::Start {
+            note,
+            project,
+            tags,
+        } => {
+            if current != SessionType::Work {
+                app.start_new_session(SessionType::Work);
+            }
+            if let Some(note) = note {
+                app.set_active_note(note);
+            }
+            if project.is_some() || !tags.is_empty() {
+                app.set_active_project(project, tags);
+            }
+        }
+        Command::Break => {
+            if current != SessionType::Break {
+                app.start_new_session(SessionType::Break);
+            }
+        }
+        Command::Stop => app.stop_working(),
+        Command::Toggle => app.toggle_work_break(),
+        Command::Note { text } => app.set_active_note(text),
+        Command::Tui
+        | Command::Status { .. }
+        | Command::Export(_)
+        | Command::Import(_)
+        | Command::Log(_)
+        | Command::ValidateSkin { .. }
+        | Command::Daemon => unreachable!("handled in main"),
+    }
+}
+
+fn describe(session: &Session) -> String {
+    let mut line = format!(
+        "{} for {}",
+        session.session_type.label(),
+        format_duration_str(session.duration())
+    );
+    let project = session.project_label();
+    if !project.is_empty() {
+        line.push_str(&format!(" [{project}]"));
+    }
+    if !session.note.is_empty() {
+        line.push_str(&format!(" ({})", session.note));
+    }
+    line
+}
+
+/// Prints the running session and today's totals without touching the log.
+pub fn print_status(store: &dyn Store, as_json: bool) -> Result<()> {
+    let (from, to) = local_day_bounds(calendar().today());
+    let today = store.sessions_between(from, to)?;
+    let active = today.last().filter(|s| s.end_time.is_none());
+
+    let mut work = Duration::zero();
+    let mut rest = Duration::zero();
+    for s in &today {
+        match s.session_type {
+            SessionType::Work => work += s.duration_between(from, to),
+            SessionType::Break => rest += s.duration_between(from, to),
+            SessionType::Idle => {}
+        }
+    }
+
+    if as_json {
+        let status = json!({
+            "session_type": active.map_or(SessionType::Idle, |s| s.session_type),
+            "started": active.map(|s| s.start_time),
+            "elapsed_seconds": active.map_or(0, |s| s.duration().num_seconds()),
+            "note": active.map_or("", |s| s.note.as_str()),
+            "project": active.and_then(|s| s.project.as_deref()),
+            "tags": active.map_or(&[][..], |s| s.tags.as_slice()),
+            "today": {
+                "work_seconds": work.num_seconds(),
+                "break_seconds": rest.num_seconds(),
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&status)?);
+    } else {
+        match active {
+            Some(session) => println!("{}", describe(session)),
+            None => println!("{}", SessionType::Idle.label()),
+        }
+        println!(
+            "Today: work {} | break {}",
+            format_duration_str(work),
+            format_duration_str(rest)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{JsonStore, read_sessions};
+    use crate::testing::{TempDir, session};
+    use std::fs;
+
+    fn parse(args: &[&str]) -> CliArgs {
+        CliArgs::parse(args.iter().map(|a| a.to_string())).unwrap()
+    }
+
+    #[test]
+    fn global_flags_come_before_the_command() {
+        let cli = parse(&["--data-file", "log.json", "--config", "c.toml", "stop"]);
+        assert_eq!(cli.data_file, Some(PathBuf::from("log.json")));
+        assert_eq!(cli.config_file, Some(PathBuf::from("c.toml")));
+        assert!(matches!(cli.command, Command::Stop));
+    }
+
+    #[test]
+    fn note_may_contain_help() {
+        let cli = parse(&["note", "please", "help", "me"]);
+        assert!(matches!(cli.command, Command::Note { text } if text == "please help me"));
+    }
+
+    #[test]
+    fn note_may_contain_global_flags() {
+        let cli = parse(&["note", "fix", "--config", "parsing"]);
+        assert_eq!(cli.config_file, None);
+        assert!(matches!(cli.command, Command::Note { text } if text == "fix --config parsing"));
+    }
+
+    #[test]
+    fn commands_on_a_closed_log_open_their_session_directly() {
+        let finished = session(
+            SessionType::Work,
+            "2024-05-06T09:00:00Z",
+            Some("2024-05-06T10:00:00Z"),
+        );
+        let start = Command::Start {
+            note: Some("api".to_string()),
+            project: None,
+            tags: Vec::new(),
+        };
+        for (command, kind) in [
+            (start, SessionType::Work),
+            (Command::Break, SessionType::Break),
+            (Command::Toggle, SessionType::Work),
+            (Command::Stop, SessionType::Idle),
+        ] {
+            let dir = TempDir::new();
+            let path = dir.join("work_log.json");
+            fs::write(&path, serde_json::to_vec(&[&finished]).unwrap()).unwrap();
+            let config = Config {
+                pet_file: Some(dir.join("pet.json")),
+                ..Config::default()
+            };
+            run(&config, Box::new(JsonStore::new(path.clone(), 0)), command).unwrap();
+
+            let (sessions, _) = read_sessions(&path).unwrap();
+            assert_eq!(sessions.len(), 2);
+            assert_eq!(sessions[0], finished);
+            assert_eq!(sessions[1].session_type, kind);
+            assert_eq!(sessions[1].end_time, None);
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use crate::calendar::{Calendar, Zone};
+use crate::cli::CliArgs;
+use crate::data::{JsonStore, Store};
+use crate::goals::GoalConfig;
+use crate::idle::IdleConfig;
+use crate::journal::JournalStore;
+use crate::pomodoro::PomodoroConfig;
+use crate::reminders::ReminderConfig;
+use anyhow::{Context, Result};
+use chrono::NaiveTime;
+use serde::Deserialize;
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const APP_DIR: &str = "rust_pet_timer";
+const DATA_FILE_NAME: &str = "work_log.json";
+pub const DATA_FILE_ENV: &str = "PET_TIMER_DATA_FILE";
+pub const CONFIG_FILE_ENV: &str = "PET_TIMER_CONFIG";
+
+/// Settings read from `config.toml`. Every field is optional.
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub data_file: Option<PathBuf>,
+    /// Unix socket the daemon listens on.
+    pub socket: Option<PathBuf>,
+    /// Where exports started from the TUI are written.
+    pub export_dir: Option<PathBuf>,
+    /// Where the pet's state is kept.
+    pub pet_file: Option<PathBuf>,
+    /// Pet skin: a file name in the skins directory (without `.toml`) or a path.
+    pub skin: Option<String>,
+    pub skins_dir: Option<PathBuf>,
+    /// Timezone days are reported in: `local`, an IANA name or an offset.
+    pub timezone: Option<String>,
+    /// When a day starts, e.g. `04:00` to count work until 4am towards
+    /// the day before.
+    pub day_starts_at: Option<String>,
+    /// How many timestamped copies of the previous log to keep.
+    pub backups: usize,
+    pub storage: StorageKind,
+    /// Journal entries written before they are folded into the snapshot.
+    pub compact_every: usize,
+    pub pomodoro: PomodoroConfig,
+    pub goals: GoalConfig,
+    pub reminders: ReminderConfig,
+    pub idle: IdleConfig,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    #[default]
+    Json,
+    Journal,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            data_file: None,
+            socket: None,
+            export_dir: None,
+            pet_file: None,
+            skin: None,
+            skins_dir: None,
+            timezone: None,
+            day_starts_at: None,
+            backups: 5,
+            storage: StorageKind::default(),
+            compact_every: 200,
+            pomodoro: PomodoroConfig::default(),
+            goals: GoalConfig::default(),
+            reminders: ReminderConfig::default(),
+            idle: IdleConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config file named by `--config`, `$PET_TIMER_CONFIG`, or the
+    /// default XDG location. A missing default file is not an error.
+    pub fn load(cli: &CliArgs) -> Result<Self> {
+        let explicit = cli
+            .config_file
+            .clone()
+            .or_else(|| env::var_os(CONFIG_FILE_ENV).map(PathBuf::from));
+        let path = match explicit {
+            Some(path) => path,
+            None => match config_dir() {
+                Some(dir) if dir.join("config.toml").exists() => dir.join("config.toml"),
+                _ => return Ok(Config::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("reading config {}", path.display()))?;
+        let config: Config =
+            toml::from_str(&text).with_context(|| format!("parsing config {}", path.display()))?;
+        config
+            .pomodoro
+            .validate()
+            .with_context(|| format!("invalid config {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// Where the session log lives: `--data-file`, then `$PET_TIMER_DATA_FILE`,
+    /// then `data_file` from the config, then the XDG data directory. Also
+    /// returns whether it is that default, the only place a log left behind
+    /// by older versions is moved to.
+    pub fn resolve_data_file(&self, cli: &CliArgs) -> (PathBuf, bool) {
+        self.data_file_from(
+            cli.data_file.clone(),
+            env::var_os(DATA_FILE_ENV),
+            data_dir(),
+        )
+    }
+
+    fn data_file_from(
+        &self,
+        cli: Option<PathBuf>,
+        env: Option<OsString>,
+        data_dir: Option<PathBuf>,
+    ) -> (PathBuf, bool) {
+        match cli
+            .or_else(|| env.map(PathBuf::from))
+            .or_else(|| self.data_file.clone())
+        {
+            Some(path) => (path, false),
+            None => match data_dir {
+                Some(dir) => (dir.join(DATA_FILE_NAME), true),
+                None => (PathBuf::from(DATA_FILE_NAME), true),
+            },
+        }
+    }
+
+    /// How days are counted: in `timezone` (else the system's), starting
+    /// at `day_starts_at` (else midnight).
+    pub fn calendar(&self) -> Result<Calendar> {
+        let zone = self
+            .timezone
+            .as_deref()
+            .map_or(Ok(Zone::Local), Zone::parse)
+            .context("invalid timezone in config")?;
+        let day_start = match &self.day_starts_at {
+            Some(time) => NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .with_context(|| format!("invalid day_starts_at {time:?}, expected HH:MM"))?,
+            None => NaiveTime::MIN,
+        };
+        Ok(Calendar::new(zone).with_day_start(day_start))
+    }
+
+    /// `export_dir` from the config, else `exports/` next to the data file.
+    pub fn resolve_export_dir(&self, data_path: &Path) -> PathBuf {
+        self.export_dir
+            .clone()
+            .unwrap_or_else(|| crate::data::parent_dir(data_path).join("exports"))
+    }
+
+    /// `pet_file` from the config, else `<log>.pet.json` next to the data file.
+    pub fn resolve_pet_file(&self, data_path: &Path) -> PathBuf {
+        self.pet_file
+            .clone()
+            .unwrap_or_else(|| data_path.with_extension("pet.json"))
+    }
+
+    /// `skins_dir` from the config, else `skins/` in the config directory.
+    pub fn resolve_skins_dir(&self) -> Option<PathBuf> {
+        self.skins_dir
+            .clone()
+            .or_else(|| config_dir().map(|dir| dir.join("skins")))
+    }
+
+    /// The daemon socket: `socket` from the config, else one per data file
+    /// in `$XDG_RUNTIME_DIR`, else next to the data file.
+    pub fn resolve_socket(&self, data_path: &Path) -> PathBuf {
+        if let Some(socket) = &self.socket {
+            return socket.clone();
+        }
+        match env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) => Path::new(&dir).join(socket_name(data_path)),
+            None => crate::data::sibling(data_path, ".sock"),
+        }
+    }
+
+    pub fn open_store(&self, data_path: PathBuf) -> Result<Box<dyn Store>> {
+        Ok(match self.storage {
+            StorageKind::Json => Box::new(JsonStore::new(data_path, self.backups)),
+            StorageKind::Journal => Box::new(JournalStore::new(
+                data_path,
+                self.backups,
+                self.compact_every,
+            )),
+            #[cfg(feature = "sqlite")]
+            StorageKind::Sqlite => Box::new(crate::sqlite::SqliteStore::open(
+                &data_path.with_extension("sqlite3"),
+                data_path,
+            )?),
+        })
+    }
+}
+
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(fallback)))
+        .map(|dir| dir.join(APP_DIR))
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Socket file name for the daemon owning `data_path`, so a client never
+/// talks to a daemon serving another log. Named after an FNV-1a hash of the
+/// canonical path, which stays the same across builds.
+fn socket_name(data_path: &Path) -> String {
+    let path = data_path.canonicalize().unwrap_or_else(|_| {
+        let dir = crate::data::parent_dir(data_path);
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        dir.join(data_path.file_name().unwrap_or_default())
+    });
+    let hash = path
+        .as_os_str()
+        .as_encoded_bytes()
+        .iter()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+        });
+    format!("{APP_DIR}-{hash:016x}.sock")
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_file_comes_from_the_cli_then_env_then_config() {
+        let xdg = Some(PathBuf::from("/xdg"));
+        let mut config = Config::default();
+        assert_eq!(
+            config.data_file_from(None, None, xdg.clone()),
+            (PathBuf::from("/xdg").join(DATA_FILE_NAME), true)
+        );
+        config.data_file = Some(PathBuf::from("/config.json"));
+        assert_eq!(
+            config.data_file_from(None, None, xdg.clone()),
+            (PathBuf::from("/config.json"), false)
+        );
+        assert_eq!(
+            config.data_file_from(None, Some("/env.json".into()), xdg.clone()),
+            (PathBuf::from("/env.json"), false)
+        );
+        assert_eq!(
+            config.data_file_from(
+                Some(PathBuf::from("/cli.json")),
+                Some("/env.json".into()),
+                xdg
+            ),
+            (PathBuf::from("/cli.json"), false)
+        );
+    }
+
+    #[test]
+    fn socket_is_named_after_the_data_file() {
+        let dir = env::temp_dir();
+        let log = dir.join("work_log.json");
+        assert_eq!(
+            socket_name(&log),
+            socket_name(&dir.join(".").join("work_log.json"))
+        );
+        assert_ne!(socket_name(&log), socket_name(&dir.join("other.json")));
+        assert!(socket_name(&log).starts_with("rust_pet_timer-"));
+    }
+}
@@ -0,0 +1,508 @@
+use crate::App;
+use crate::cli::{self, Command};
+use crate::config::Config;
+use crate::data::{Incoming, LoadedLog, LogEvent, Session, Store};
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    fs,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    net::Shutdown,
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+/// A client whose socket stays full this long is disconnected.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One JSON object per line from client to daemon.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    Snapshot,
+    /// Snapshot, then an `events` message whenever another client changes the log.
+    Subscribe,
+    /// Apply changes made against version `base` of the log.
+    Apply {
+        base: u64,
+        events: Vec<LogEvent>,
+    },
+    Range {
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    },
+    Start {
+        note: Option<String>,
+        #[serde(default)]
+        project: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    Break,
+    Stop,
+    Toggle,
+    Note {
+        text: String,
+    },
+}
+
+/// One JSON object per line from daemon to client.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Snapshot {
+        version: u64,
+        sessions: Vec<Session>,
+    },
+    Applied {
+        version: u64,
+    },
+    /// `base` was stale: someone else changed the log first.
+    Conflict {
+        version: u64,
+    },
+    Sessions {
+        sessions: Vec<Session>,
+    },
+    Active {
+        version: u64,
+        session: Session,
+    },
+    Error {
+        message: String,
+    },
+    /// Pushed to subscribers when another client changed the log.
+    Events {
+        version: u64,
+        events: Vec<LogEvent>,
+    },
+}
+
+/// A request or reply with the id the client gave it, so a reply that
+/// arrives after the client gave up waiting is not taken for the next one.
+/// Scripts may leave it out.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    #[serde(flatten)]
+    body: T,
+}
+
+fn encode<T: Serialize>(message: &T) -> Result<String> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    Ok(line)
+}
+
+fn send<T: Serialize>(mut stream: &UnixStream, message: &T) -> Result<()> {
+    stream.write_all(encode(message)?.as_bytes())?;
+    Ok(())
+}
+
+// --- Daemon ---
+
+/// Wraps the real store so every event the daemon's `App` records can be
+/// forwarded to subscribers.
+struct Broadcasting {
+    inner: Box<dyn Store>,
+    outbox: Arc<Mutex<Vec<LogEvent>>>,
+}
+
+impl Store for Broadcasting {
+    fn load(&mut self) -> Result<LoadedLog> {
+        self.inner.load()
+    }
+
+    fn record(&mut self, sessions: &[Session], events: &[LogEvent]) -> Result<()> {
+        self.outbox.lock().unwrap().extend_from_slice(events);
+        self.inner.record(sessions, events)
+    }
+
+    fn sessions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Session>> {
+        self.inner.sessions_between(from, to)
+    }
+}
+
+struct Shared {
+    app: App,
+    outbox: Arc<Mutex<Vec<LogEvent>>>,
+    version: u64,
+    /// Outgoing queues of the clients that subscribed.
+    subscribers: Vec<(usize, Sender<String>)>,
+}
+
+impl Shared {
+    /// Bumps the version and sends what was recorded to every subscriber but
+    /// the client that caused it.
+    fn publish(&mut self, origin: usize) {
+        let events = std::mem::take(&mut *self.outbox.lock().unwrap());
+        if events.is_empty() {
+            return;
+        }
+        self.version += 1;
+        let message = Response::Events {
+            version: self.version,
+            events,
+        };
+        let Ok(line) = encode(&message) else {
+            return;
+        };
+        self.subscribers
+            .retain(|(id, outgoing)| *id == origin || outgoing.send(line.clone()).is_ok());
+    }
+
+    fn handle(
+        &mut self,
+        conn: usize,
+        outgoing: &Sender<String>,
+        request: Request,
+    ) -> Result<Response> {
+        let command = match request {
+            Request::Snapshot => return Ok(self.snapshot()),
+            Request::Subscribe => {
+                self.subscribers.push((conn, outgoing.clone()));
+                return Ok(self.snapshot());
+            }
+            Request::Apply { base, events } => {
+                if base != self.version {
+                    return Ok(Response::Conflict {
+                        version: self.version,
+                    });
+                }
+                self.app.apply_events(&events)?;
+                self.app.record(&events);
+                self.publish(conn);
+                self.app.flush()?;
+                return Ok(Response::Applied {
+                    version: self.version,
+                });
+            }
+            Request::Range { from, to } => {
+                return Ok(Response::Sessions {
+                    sessions: self.app.store.sessions_between(from, to)?,
+                });
+            }
+            Request::Start {
+                note,
+                project,
+                tags,
+            } => Command::Start {
+                note,
+                project,
+                tags,
+            },
+            Request::Break => Command::Break,
+            Request::Stop => Command::Stop,
+            Request::Toggle => Command::Toggle,
+            Request::Note { text } => Command::Note { text },
+        };
+        cli::apply_command(&mut self.app, command);
+        self.publish(conn);
+        self.app.flush()?;
+        Ok(Response::Active {
+            version: self.version,
+            session: self.app.get_active_session().clone(),
+        })
+    }
+
+    fn snapshot(&self) -> Response {
+        Response::Snapshot {
+            version: self.version,
+            sessions: self.app.sessions.clone(),
+        }
+    }
+}
+
+/// Runs the daemon in the foreground, owning the log until killed.
+pub fn run(config: &Config, store: Box<dyn Store>, socket: &Path) -> Result<()> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            bail!("a daemon is already listening on {}", socket.display());
+        }
+        fs::remove_file(socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+
+    let outbox = Arc::new(Mutex::new(Vec::new()));
+    let app = App::new(
+        config,
+        Box::new(Broadcasting {
+            inner: store,
+            outbox: outbox.clone(),
+        }),
+    )?;
+    outbox.lock().unwrap().clear();
+    let shared = Arc::new(Mutex::new(Shared {
+        app,
+        outbox,
+        version: 0,
+        subscribers: Vec::new(),
+    }));
+    eprintln!("listening on {}", socket.display());
+
+    for (conn, stream) in listener.incoming().enumerate() {
+        let stream = stream?;
+        let shared = shared.clone();
+        thread::spawn(move || {
+            if let Err(err) = serve(&shared, conn, stream) {
+                eprintln!("client {conn}: {err:#}");
+            }
+            shared
+                .lock()
+                .unwrap()
+                .subscribers
+                .retain(|(id, _)| *id != conn);
+        });
+    }
+    Ok(())
+}
+
+fn serve(shared: &Mutex<Shared>, conn: usize, stream: UnixStream) -> Result<()> {
+    let outgoing = spawn_writer(stream.try_clone()?)?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut shared = shared.lock().unwrap();
+        let (id, request) = match serde_json::from_str::<Envelope<Request>>(&line) {
+            Ok(request) => (request.id, Ok(request.body)),
+            Err(err) => (None, Err(err.into())),
+        };
+        let response = request
+            .and_then(|request| shared.handle(conn, &outgoing, request))
+            .unwrap_or_else(|err| Response::Error {
+                message: format!("{err:#}"),
+            });
+        let reply = Envelope { id, body: response };
+        // Queued under the lock so replies keep their order with broadcasts
+        if outgoing.send(encode(&reply)?).is_err() {
+            bail!("client stopped reading");
+        }
+    }
+    Ok(())
+}
+
+/// Writes a client's replies and broadcasts on a thread of its own, so one
+/// that stops reading holds up nobody else. It is disconnected once its
+/// socket has been full for `WRITE_TIMEOUT`.
+fn spawn_writer(stream: UnixStream) -> Result<Sender<String>> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let (outgoing, queue) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for line in queue {
+            if (&stream).write_all(line.as_bytes()).is_err() {
+                let _ = stream.shutdown(Shutdown::Both);
+                break;
+            }
+        }
+    });
+    Ok(outgoing)
+}
+
+// --- Client ---
+
+/// Store used by the TUI and CLI while a daemon is running: changes are
+/// sent to the daemon, which owns the log. Each change names the version it
+/// was made against, so a client that missed someone else's change gets a
+/// conflict and reloads instead of clobbering it.
+pub struct RemoteStore {
+    stream: UnixStream,
+    replies: Receiver<Envelope<Response>>,
+    updates: Receiver<Response>,
+    /// Id of the last request sent.
+    last_id: Cell<u64>,
+    version: u64,
+    resync: bool,
+}
+
+impl RemoteStore {
+    /// Connects to the daemon, or returns `None` if none is listening.
+    pub fn connect(socket: &Path) -> Result<Option<Self>> {
+        let stream = match UnixStream::connect(socket) {
+            Ok(stream) => stream,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorKind::NotFound | ErrorKind::ConnectionRefused
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let (reply_tx, replies) = mpsc::channel();
+        let (update_tx, updates) = mpsc::channel();
+        let reader = BufReader::new(stream.try_clone()?);
+        thread::spawn(move || {
+            for line in reader.lines().map_while(Result::ok) {
+                let Ok(message) = serde_json::from_str::<Envelope<Response>>(&line) else {
+                    continue;
+                };
+                let sent = match message.body {
+                    Response::Events { .. } => update_tx.send(message.body).is_ok(),
+                    _ => reply_tx.send(message).is_ok(),
+                };
+                if !sent {
+                    break;
+                }
+            }
+        });
+        Ok(Some(RemoteStore {
+            stream,
+            replies,
+            updates,
+            last_id: Cell::new(0),
+            version: 0,
+            resync: false,
+        }))
+    }
+
+    fn request(&self, request: &Request) -> Result<Response> {
+        let id = self.last_id.get() + 1;
+        self.last_id.set(id);
+        // Replies to requests that timed out are of no use any more
+        while self.replies.try_recv().is_ok() {}
+        send(
+            &self.stream,
+            &Envelope {
+                id: Some(id),
+                body: request,
+            },
+        )?;
+        let deadline = Instant::now() + REPLY_TIMEOUT;
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            match self.replies.recv_timeout(wait) {
+                Ok(reply) if reply.id != Some(id) => continue,
+                Ok(Envelope {
+                    body: Response::Error { message },
+                    ..
+                }) => return Err(anyhow!(message)),
+                Ok(reply) => return Ok(reply.body),
+                Err(_) => bail!("the daemon did not answer"),
+            }
+        }
+    }
+}
+
+fn unexpected(response: Response) -> anyhow::Error {
+    anyhow!("unexpected reply from daemon: {response:?}")
+}
+
+impl Store for RemoteStore {
+    fn load(&mut self) -> Result<LoadedLog> {
+        match self.request(&Request::Subscribe)? {
+            Response::Snapshot { version, sessions } => {
+                self.version = version;
+                Ok(LoadedLog {
+                    sessions,
+                    recovered_from: None,
+                })
+            }
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn record(&mut self, _sessions: &[Session], events: &[LogEvent]) -> Result<()> {
+        let request = Request::Apply {
+            base: self.version,
+            events: events.to_vec(),
+        };
+        match self.request(&request)? {
+            Response::Applied { version } => {
+                self.version = version;
+                Ok(())
+            }
+            Response::Conflict { .. } => {
+                self.resync = true;
+                bail!("another client changed the log first, reloading")
+            }
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn sessions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Session>> {
+        match self.request(&Request::Range { from, to })? {
+            Response::Sessions { sessions } => Ok(sessions),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    fn incoming(&mut self) -> Vec<Incoming> {
+        let mut updates = Vec::new();
+        while !self.resync {
+            let Ok(Response::Events { version, events }) = self.updates.try_recv() else {
+                break;
+            };
+            if version <= self.version {
+                continue;
+            }
+            if version != self.version + 1 {
+                // Missed something: only a full snapshot can fix that
+                self.resync = true;
+                break;
+            }
+            self.version = version;
+            updates.push(Incoming::Events(events));
+        }
+
+        if self.resync {
+            while self.updates.try_recv().is_ok() {}
+            if let Ok(Response::Snapshot { version, sessions }) = self.request(&Request::Snapshot) {
+                self.version = version;
+                self.resync = false;
+                updates.clear();
+                updates.push(Incoming::Reset(sessions));
+            }
+        }
+        updates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_may_leave_out_the_id() {
+        let request: Envelope<Request> = serde_json::from_str(r#"{"cmd":"toggle"}"#).unwrap();
+        assert_eq!(request.id, None);
+        assert!(matches!(request.body, Request::Toggle));
+
+        let request: Envelope<Request> =
+            serde_json::from_str(r#"{"id":7,"cmd":"note","text":"hi"}"#).unwrap();
+        assert_eq!(request.id, Some(7));
+        assert!(matches!(request.body, Request::Note { text } if text == "hi"));
+    }
+
+    #[test]
+    fn replies_carry_the_request_id() {
+        let line = encode(&Envelope {
+            id: Some(3),
+            body: Response::Applied { version: 2 },
+        })
+        .unwrap();
+        assert_eq!(line, "{\"id\":3,\"type\":\"applied\",\"version\":2}\n");
+        let reply: Envelope<Response> = serde_json::from_str(&line).unwrap();
+        assert_eq!(reply.id, Some(3));
+        assert!(matches!(reply.body, Response::Applied { version: 2 }));
+    }
+}
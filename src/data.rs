@@ -1,10 +1,18 @@
-use anyhow::Result;
-use chrono::{DateTime, Duration, Local, Utc};
+use crate::calendar::{Zone, calendar};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
 use ratatui::style::Color;
-use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs::{self, File, OpenOptions, TryLockError},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
 
-const DB_PATH: &str = "work_log.json";
+/// Where older versions kept the log: relative to the launch directory.
+const LEGACY_DB_PATH: &str = "work_log.json";
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SessionType {
@@ -31,15 +39,49 @@ impl SessionType {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Session {
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
     pub session_type: SessionType,
     pub note: String,
+    // Both optional so logs written before projects existed still load
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Timezone the session was recorded in, an IANA name or UTC offset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    /// Set once an edit moves the start, see `uid()`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
 }
 
 impl Session {
+    /// A `kind` session running from `start`, recorded in the local timezone.
+    pub fn new(kind: SessionType, start: DateTime<Utc>) -> Self {
+        Session {
+            start_time: start,
+            end_time: None,
+            session_type: kind,
+            note: String::new(),
+            project: None,
+            tags: Vec::new(),
+            zone: Some(Zone::Local.name()),
+            uid: None,
+        }
+    }
+
+    /// Identifies the session in exported calendars. It comes from the start
+    /// time, which an edit keeps in `uid` before moving it, so editing or
+    /// splitting a session updates its event instead of adding another.
+    pub fn uid(&self) -> String {
+        self.uid
+            .clone()
+            .unwrap_or_else(|| self.start_time.timestamp_micros().to_string())
+    }
+
     pub fn duration(&self) -> Duration {
         match self.end_time {
             Some(end) => end - self.start_time,
@@ -47,40 +89,828 @@ impl Session {
         }
     }
 
-    pub fn start_time_local(&self) -> DateTime<Local> {
-        DateTime::from(self.start_time)
+    /// Start time in the reporting timezone.
+    pub fn start_time_local(&self) -> DateTime<FixedOffset> {
+        calendar().zone.local_time(self.start_time)
+    }
+
+    pub fn end_time_local(&self) -> Option<DateTime<FixedOffset>> {
+        self.end_time.map(|t| calendar().zone.local_time(t))
+    }
+
+    pub fn overlaps(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> bool {
+        self.start_time < to && self.end_time.is_none_or(|end| end > from)
+    }
+
+    /// Part of the session between `from` and `to`, zero if it lies outside.
+    pub fn duration_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Duration {
+        let end = self.end_time.unwrap_or_else(Utc::now).min(to);
+        (end - self.start_time.max(from)).max(Duration::zero())
+    }
+
+    /// The part of the session that falls on the local day `date`.
+    pub fn clip_to_day(&self, date: NaiveDate) -> DayClip {
+        let (from, to) = local_day_bounds(date);
+        self.clip(from, to)
+    }
+
+    /// The part of the session between the day bounds `from` and `to`.
+    fn clip(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> DayClip {
+        DayClip {
+            duration: self.duration_between(from, to),
+            from_previous_day: self.start_time < from,
+            into_next_day: self.end_time.unwrap_or_else(Utc::now) > to,
+        }
     }
 
-    pub fn end_time_local(&self) -> Option<DateTime<Local>> {
-        self.end_time.map(DateTime::from)
+    /// Project and tags as typed in the picker, e.g. `website #frontend`.
+    pub fn project_label(&self) -> String {
+        self.project
+            .iter()
+            .cloned()
+            .chain(self.tags.iter().map(|t| format!("#{t}")))
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 }
 
-pub fn load_sessions() -> Result<Vec<Session>> {
-    let mut sessions: Vec<Session> = if Path::new(DB_PATH).exists() {
-        let data = fs::read_to_string(DB_PATH)?;
-        serde_json::from_str(&data)?
-    } else {
-        Vec::new()
+/// A session's share of one day. Sessions running past midnight count
+/// towards each day only with the time spent on it.
+pub struct DayClip {
+    pub duration: Duration,
+    /// Started on an earlier day.
+    pub from_previous_day: bool,
+    /// Runs on into the next day.
+    pub into_next_day: bool,
+}
+
+/// Splits picker input like `website #frontend #urgent` into a project and
+/// its tags. Words starting with `#` are tags, the rest name the project.
+pub fn parse_project_label(input: &str) -> (Option<String>, Vec<String>) {
+    let (tags, words): (Vec<&str>, Vec<&str>) =
+        input.split_whitespace().partition(|w| w.starts_with('#'));
+    let tags = tags
+        .iter()
+        .map(|t| t.trim_start_matches('#'))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect();
+    let project = (!words.is_empty()).then(|| words.join(" "));
+    (project, tags)
+}
+
+/// Start and end of `date` in the reporting timezone.
+pub fn local_day_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    calendar().day_bounds(date)
+}
+
+/// Lookups by time into a log sorted by start time. Imported sessions can
+/// overlap the rest of the log, so a session may reach past several later
+/// ones; the latest end up to each session never decreases, which finds the
+/// first one still running at a given time by binary search.
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    latest_end: Vec<DateTime<Utc>>,
+}
+
+impl Timeline {
+    pub fn new(sessions: &[Session]) -> Self {
+        let latest_end = sessions
+            .iter()
+            .scan(DateTime::<Utc>::MIN_UTC, |latest, s| {
+                *latest = (*latest).max(s.end_time.unwrap_or(DateTime::<Utc>::MAX_UTC));
+                Some(*latest)
+            })
+            .collect();
+        Timeline { latest_end }
+    }
+
+    /// Indices into `sessions` of the sessions on the local day `date`. A
+    /// session still running at midnight is part of both days.
+    pub fn day_indices(&self, sessions: &[Session], date: NaiveDate) -> Vec<usize> {
+        let (from, to) = local_day_bounds(date);
+        self.overlapping(sessions, from, to)
+    }
+
+    /// Indices into `sessions`, the log this was built from, of the sessions
+    /// overlapping `from..to`.
+    pub fn overlapping(
+        &self,
+        sessions: &[Session],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<usize> {
+        debug_assert_eq!(self.latest_end.len(), sessions.len());
+        let first = self.latest_end.partition_point(|end| *end <= from);
+        let lo = sessions.partition_point(|s| s.start_time < from);
+        let hi = sessions.partition_point(|s| s.start_time < to).max(lo);
+        (first..lo)
+            .filter(|&i| sessions[i].overlaps(from, to))
+            .chain(lo..hi)
+            .collect()
+    }
+}
+
+/// Result of reading the log. `recovered_from` is set when the main file
+/// could not be parsed and the sessions came from a backup instead.
+pub struct LoadedLog {
+    pub sessions: Vec<Session>,
+    pub recovered_from: Option<PathBuf>,
+}
+
+/// Reads the pretty-JSON log under its lock, for importing it into another backend.
+#[cfg(feature = "sqlite")]
+pub fn load_sessions(path: &Path) -> Result<LoadedLog> {
+    let _lock = lock_log(path)?;
+    let (mut sessions, recovered_from) = read_sessions(path)?;
+    close_open_sessions(&mut sessions);
+    Ok(LoadedLog {
+        sessions,
+        recovered_from,
+    })
+}
+
+/// The log as stored, oldest first. Callers must hold the log lock.
+pub fn read_sessions(path: &Path) -> Result<(Vec<Session>, Option<PathBuf>)> {
+    let (mut sessions, recovered_from) = load_with_recovery::<Vec<Session>>(path)?;
+    sessions.sort_by_key(|s| s.start_time);
+    Ok((sessions, recovered_from))
+}
+
+/// Takes the advisory lock on `<log>.lock`, released when the file drops.
+/// A separate file is locked because the log itself is replaced on save.
+pub fn lock_log(path: &Path) -> Result<File> {
+    fs::create_dir_all(parent_dir(path))?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(sibling(path, ".lock"))?;
+    file.lock()?;
+    Ok(file)
+}
+
+/// Hash of the log's contents, `None` if it does not exist.
+fn fingerprint(path: &Path) -> Result<Option<u64>> {
+    match fs::read(path) {
+        Ok(bytes) => {
+            let mut hasher = DefaultHasher::new();
+            bytes.hash(&mut hasher);
+            Ok(Some(hasher.finish()))
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Three-way merge keyed by start time, and by position among sessions that
+/// share one. `base` is what both sides started from; a side that changed or
+/// removed a session wins over one that left it alone, and our edits win
+/// when both changed it. Sessions added on both sides are all kept.
+pub fn merge_sessions(base: &[Session], ours: &[Session], theirs: &[Session]) -> Vec<Session> {
+    let keyed = |list: &[Session]| -> BTreeMap<(DateTime<Utc>, usize), Session> {
+        let mut seen = HashMap::new();
+        list.iter()
+            .map(|s| {
+                let n = seen.entry(s.start_time).or_insert(0);
+                *n += 1;
+                ((s.start_time, *n), s.clone())
+            })
+            .collect()
     };
+    let base = keyed(base);
+    let mut ours = keyed(ours);
+    let mut theirs = keyed(theirs);
+    let keys: BTreeSet<_> = ours.keys().chain(theirs.keys()).copied().collect();
+
+    let mut merged = Vec::new();
+    for key in keys {
+        match (base.get(&key), ours.remove(&key), theirs.remove(&key)) {
+            (Some(b), Some(o), Some(t)) => merged.push(if o == *b { t } else { o }),
+            // Deleted on one side: keep only if the other side edited it
+            (Some(b), Some(o), None) => merged.extend((o != *b).then_some(o)),
+            (Some(b), None, Some(t)) => merged.extend((t != *b).then_some(t)),
+            (None, Some(o), Some(t)) => {
+                if o != t {
+                    merged.push(t);
+                }
+                merged.push(o);
+            }
+            (None, Some(o), None) => merged.push(o),
+            (None, None, Some(t)) => merged.push(t),
+            (_, None, None) => {}
+        }
+    }
+    merged
+}
 
+/// Sessions left open by a previous run end where the next one starts, or at
+/// their own start if they are too old to be trusted. The newest session is
+/// left running so a timer started from the CLI survives between runs.
+pub fn close_open_sessions(sessions: &mut [Session]) {
     let now = Utc::now();
-    for session in &mut sessions {
+    let next_starts: Vec<Option<DateTime<Utc>>> = sessions
+        .iter()
+        .skip(1)
+        .map(|s| Some(s.start_time))
+        .chain([None])
+        .collect();
+    for (session, next_start) in sessions.iter_mut().zip(next_starts) {
         if session.end_time.is_none() {
             let duration = now - session.start_time;
             if duration > Duration::hours(24) {
                 session.end_time = Some(session.start_time);
                 session.note.push_str(" [Auto-closed: Stale]");
-            } else {
-                session.end_time = Some(now);
+            } else if next_start.is_some() {
+                session.end_time = next_start;
             }
         }
     }
-    Ok(sessions)
 }
 
-pub fn save_sessions(sessions: &[Session]) -> Result<()> {
-    let data = serde_json::to_string_pretty(sessions)?;
-    fs::write(DB_PATH, data)?;
+/// Reads a JSON file, falling back to the newest readable backup when it
+/// does not parse. A missing file yields `T::default()`.
+pub fn load_with_recovery<T: DeserializeOwned + Default>(
+    path: &Path,
+) -> Result<(T, Option<PathBuf>)> {
+    match read_json(path) {
+        Ok(value) => Ok((value, None)),
+        Err(err) => {
+            let (backup, value) = newest_readable_backup(path).ok_or_else(|| {
+                err.context(format!(
+                    "{} is unreadable and no usable backup was found",
+                    path.display()
+                ))
+            })?;
+            // Keep the broken file around instead of overwriting it on the next save
+            let stamp = Utc::now().format(BACKUP_STAMP);
+            fs::rename(path, sibling(path, &format!(".corrupt-{stamp}")))?;
+            Ok((value, Some(backup)))
+        }
+    }
+}
+
+fn read_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+    let data = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+/// Writes a file crash-safely: the previous file is copied into the backup
+/// directory, the new contents go to a temp file that is fsynced and then
+/// renamed over the original.
+pub fn write_atomic(path: &Path, data: &[u8], keep_backups: usize) -> Result<()> {
+    let dir = parent_dir(path);
+    fs::create_dir_all(dir)?;
+
+    if keep_backups > 0 && path.exists() {
+        backup_file(path, keep_backups)?;
+    }
+
+    let tmp = sibling(path, ".tmp");
+    {
+        let mut file = File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+const BACKUP_STAMP: &str = "%Y%m%dT%H%M%S%3f";
+
+pub fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    }
+}
+
+/// `path` with `suffix` appended to its file name.
+pub fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn backup_dir(path: &Path) -> PathBuf {
+    parent_dir(path).join("backups")
+}
+
+fn backup_prefix(path: &Path) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    format!("{stem}-")
+}
+
+/// Backups of `path`, oldest first. The timestamp in the name sorts
+/// chronologically.
+fn list_backups(path: &Path) -> Vec<PathBuf> {
+    let prefix = backup_prefix(path);
+    let Ok(entries) = fs::read_dir(backup_dir(path)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".json"))
+        })
+        .collect();
+    backups.sort();
+    backups
+}
+
+fn backup_file(path: &Path, keep: usize) -> Result<()> {
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)?;
+    let stamp = Utc::now().format(BACKUP_STAMP);
+    fs::copy(
+        path,
+        dir.join(format!("{}{stamp}.json", backup_prefix(path))),
+    )?;
+
+    let backups = list_backups(path);
+    let excess = backups.len().saturating_sub(keep);
+    for old in &backups[..excess] {
+        fs::remove_file(old)?;
+    }
     Ok(())
 }
+
+fn newest_readable_backup<T: DeserializeOwned + Default>(path: &Path) -> Option<(PathBuf, T)> {
+    list_backups(path)
+        .into_iter()
+        .rev()
+        .find_map(|backup| read_json(&backup).ok().map(|v| (backup, v)))
+}
+
+/// One-time move of a `work_log.json` in the current directory to `target`.
+pub fn migrate_legacy_log(target: &Path) -> Result<bool> {
+    migrate_log(Path::new(LEGACY_DB_PATH), target)
+}
+
+/// Copies `legacy` to `target` unless that exists already, and renames it to
+/// `<legacy>.migrated` so it is not picked up again.
+fn migrate_log(legacy: &Path, target: &Path) -> Result<bool> {
+    if target.exists() || !legacy.exists() {
+        return Ok(false);
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(legacy, target)?;
+    fs::rename(legacy, sibling(legacy, ".migrated"))?;
+    Ok(true)
+}
+
+/// A change to the session list, in the order the app made it. Indices refer
+/// to the list as it was just before the event was applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LogEvent {
+    Started {
+        session: Session,
+    },
+    Ended {
+        index: usize,
+        end_time: DateTime<Utc>,
+    },
+    NoteChanged {
+        index: usize,
+        note: String,
+    },
+    Deleted {
+        index: usize,
+    },
+    ProjectChanged {
+        index: usize,
+        project: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    },
+    /// A finished session added in the middle of the history, e.g. by an
+    /// import. `index` keeps the list sorted by start time.
+    Inserted {
+        index: usize,
+        session: Session,
+    },
+    /// A session edited by hand. Edits that move it past its neighbours
+    /// are recorded as `Deleted` and `Inserted` instead.
+    Replaced {
+        index: usize,
+        session: Session,
+    },
+}
+
+impl LogEvent {
+    pub fn apply(&self, sessions: &mut Vec<Session>) -> Result<()> {
+        let len = sessions.len();
+        let out_of_range = |index: usize| anyhow!("event index {index} out of range ({len})");
+        match self {
+            LogEvent::Started { session } => sessions.push(session.clone()),
+            LogEvent::Ended { index, end_time } => {
+                sessions
+                    .get_mut(*index)
+                    .ok_or_else(|| out_of_range(*index))?
+                    .end_time = Some(*end_time);
+            }
+            LogEvent::NoteChanged { index, note } => {
+                sessions
+                    .get_mut(*index)
+                    .ok_or_else(|| out_of_range(*index))?
+                    .note = note.clone();
+            }
+            LogEvent::ProjectChanged {
+                index,
+                project,
+                tags,
+            } => {
+                let session = sessions
+                    .get_mut(*index)
+                    .ok_or_else(|| out_of_range(*index))?;
+                session.project = project.clone();
+                session.tags = tags.clone();
+            }
+            LogEvent::Deleted { index } => {
+                if *index >= len {
+                    return Err(out_of_range(*index));
+                }
+                sessions.remove(*index);
+            }
+            LogEvent::Inserted { index, session } => {
+                if *index > len {
+                    return Err(out_of_range(*index));
+                }
+                sessions.insert(*index, session.clone());
+            }
+            LogEvent::Replaced { index, session } => {
+                *sessions
+                    .get_mut(*index)
+                    .ok_or_else(|| out_of_range(*index))? = session.clone();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where sessions are persisted. `record` is called after the in-memory list
+/// has been updated, with the events that led to it.
+pub trait Store: Send {
+    fn load(&mut self) -> Result<LoadedLog>;
+    fn record(&mut self, sessions: &[Session], events: &[LogEvent]) -> Result<()>;
+    /// Stored sessions overlapping `[from, to)`, oldest first.
+    fn sessions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Session>>;
+    /// Changes made by someone else since the last call. Only stores shared
+    /// through the daemon ever have any.
+    fn incoming(&mut self) -> Vec<Incoming> {
+        Vec::new()
+    }
+    /// Something the user should know about the shared state of the log.
+    fn warning(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A change to apply to the in-memory list that did not originate locally.
+pub enum Incoming {
+    Events(Vec<LogEvent>),
+    /// The local copy is out of sync and must be replaced wholesale.
+    Reset(Vec<Session>),
+}
+
+/// The original backend: the whole list as pretty JSON, rewritten on every change.
+///
+/// Reads and writes happen under an advisory lock. If the file changed since
+/// we last wrote it, another instance is using it too: its sessions are
+/// merged in by start time instead of being overwritten.
+pub struct JsonStore {
+    path: PathBuf,
+    keep_backups: usize,
+    /// The list as the app last saw it, the base of the three-way merge.
+    base: Vec<Session>,
+    /// Contents of the file as we last read or wrote it.
+    fingerprint: Option<u64>,
+    /// Held for as long as we run; `None` if another instance has it.
+    instance: Option<File>,
+    merged: bool,
+    reset: Option<Vec<Session>>,
+}
+
+impl JsonStore {
+    pub fn new(path: PathBuf, keep_backups: usize) -> Self {
+        JsonStore {
+            path,
+            keep_backups,
+            base: Vec::new(),
+            fingerprint: None,
+            instance: None,
+            merged: false,
+            reset: None,
+        }
+    }
+
+    fn claim_instance(&mut self) -> Result<()> {
+        fs::create_dir_all(parent_dir(&self.path))?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(sibling(&self.path, ".instance"))?;
+        self.instance = match file.try_lock() {
+            Ok(()) => Some(file),
+            Err(TryLockError::WouldBlock) => None,
+            Err(TryLockError::Error(err)) => return Err(err.into()),
+        };
+        Ok(())
+    }
+}
+
+impl Store for JsonStore {
+    fn load(&mut self) -> Result<LoadedLog> {
+        self.claim_instance()?;
+        let _lock = lock_log(&self.path)?;
+        let (mut sessions, recovered_from) = read_sessions(&self.path)?;
+        self.fingerprint = fingerprint(&self.path)?;
+        self.base = sessions.clone();
+        close_open_sessions(&mut sessions);
+        Ok(LoadedLog {
+            sessions,
+            recovered_from,
+        })
+    }
+
+    fn record(&mut self, sessions: &[Session], _events: &[LogEvent]) -> Result<()> {
+        let _lock = lock_log(&self.path)?;
+        let mut to_write = sessions.to_vec();
+        if self.reset.is_some() || fingerprint(&self.path)? != self.fingerprint {
+            let (theirs, _) = read_sessions(&self.path)?;
+            to_write = merge_sessions(&self.base, sessions, &theirs);
+            if to_write != sessions {
+                self.merged = true;
+                self.reset = Some(to_write.clone());
+            }
+        }
+        let data = serde_json::to_string_pretty(&to_write)?;
+        write_atomic(&self.path, data.as_bytes(), self.keep_backups)?;
+        self.fingerprint = fingerprint(&self.path)?;
+        if self.reset.is_none() {
+            self.base = to_write;
+        }
+        Ok(())
+    }
+
+    fn incoming(&mut self) -> Vec<Incoming> {
+        match self.reset.take() {
+            Some(sessions) => {
+                self.base = sessions.clone();
+                vec![Incoming::Reset(sessions)]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn warning(&self) -> Option<String> {
+        if self.merged {
+            Some("Another instance changed the log, its sessions were merged".to_string())
+        } else if self.instance.is_none() {
+            Some("Another instance is using this log".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn sessions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Session>> {
+        // Read like `load`, without claiming the log or writing anything back
+        let (mut sessions, _) = {
+            let _lock = lock_log(&self.path)?;
+            read_sessions(&self.path)?
+        };
+        close_open_sessions(&mut sessions);
+        Ok(sessions
+            .into_iter()
+            .filter(|s| s.overlaps(from, to))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TempDir, session, utc};
+
+    /// Bounds of 2024-05-05 in UTC.
+    fn day() -> (DateTime<Utc>, DateTime<Utc>) {
+        (utc("2024-05-05T00:00:00Z"), utc("2024-05-06T00:00:00Z"))
+    }
+
+    #[test]
+    fn project_labels_split_into_project_and_tags() {
+        let tags = |t: &[&str]| t.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_project_label("  client   website #frontend #urgent"),
+            (Some("client website".into()), tags(&["frontend", "urgent"]))
+        );
+        // Tags may come before or between the words of the project
+        assert_eq!(
+            parse_project_label("#ops on call #pager"),
+            (Some("on call".into()), tags(&["ops", "pager"]))
+        );
+        // A bare `#` is no tag, and tags alone leave the project unset
+        assert_eq!(
+            parse_project_label("# #meeting"),
+            (None, tags(&["meeting"]))
+        );
+        assert_eq!(parse_project_label("   "), (None, vec![]));
+    }
+
+    #[test]
+    fn sessions_over_midnight_count_on_each_day() {
+        let (from, to) = day();
+        let evening = session(
+            SessionType::Work,
+            "2024-05-05T23:00:00Z",
+            Some("2024-05-06T02:00:00Z"),
+        );
+        let clip = evening.clip(from, to);
+        assert_eq!(clip.duration, Duration::hours(1));
+        assert!(!clip.from_previous_day && clip.into_next_day);
+        let clip = evening.clip(to, to + Duration::days(1));
+        assert_eq!(clip.duration, Duration::hours(2));
+        assert!(clip.from_previous_day && !clip.into_next_day);
+
+        let away = session(
+            SessionType::Idle,
+            "2024-05-04T20:00:00Z",
+            Some("2024-05-06T08:00:00Z"),
+        );
+        let clip = away.clip(from, to);
+        assert_eq!(clip.duration, Duration::days(1));
+        assert!(clip.from_previous_day && clip.into_next_day);
+        assert_eq!(
+            away.clip(to + Duration::days(1), to + Duration::days(2))
+                .duration,
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn earlier_sessions_reach_in_past_overlapping_ones() {
+        let (from, to) = day();
+        let sessions = vec![
+            session(
+                SessionType::Idle,
+                "2024-05-04T20:00:00Z",
+                Some("2024-05-05T08:00:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-04T21:00:00Z",
+                Some("2024-05-04T21:30:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-04T22:00:00Z",
+                Some("2024-05-04T23:00:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-05T09:00:00Z",
+                Some("2024-05-05T12:00:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-05T23:00:00Z",
+                Some("2024-05-06T01:00:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-06T09:00:00Z",
+                Some("2024-05-06T10:00:00Z"),
+            ),
+        ];
+        let timeline = Timeline::new(&sessions);
+        assert_eq!(timeline.overlapping(&sessions, from, to), [0, 3, 4]);
+        let next = (to, to + Duration::days(1));
+        assert_eq!(timeline.overlapping(&sessions, next.0, next.1), [4, 5]);
+        let after = (next.1, next.1 + Duration::days(1));
+        assert!(timeline.overlapping(&sessions, after.0, after.1).is_empty());
+    }
+
+    #[test]
+    fn a_running_session_reaches_every_later_day() {
+        let sessions = vec![
+            session(
+                SessionType::Work,
+                "2024-05-03T09:00:00Z",
+                Some("2024-05-03T10:00:00Z"),
+            ),
+            session(SessionType::Idle, "2024-05-04T18:00:00Z", None),
+        ];
+        let timeline = Timeline::new(&sessions);
+        let (from, to) = day();
+        assert_eq!(timeline.overlapping(&sessions, from, to), [1]);
+        let earlier = (from - Duration::days(2), to - Duration::days(2));
+        assert_eq!(timeline.overlapping(&sessions, earlier.0, earlier.1), [0]);
+    }
+
+    fn noted(start: &str, end: &str, note: &str) -> Session {
+        Session {
+            note: note.to_string(),
+            ..session(SessionType::Work, start, Some(end))
+        }
+    }
+
+    #[test]
+    fn merge_keeps_edits_over_deletes() {
+        let a = noted("2024-05-05T09:00:00Z", "2024-05-05T10:00:00Z", "a");
+        let b = noted("2024-05-05T11:00:00Z", "2024-05-05T12:00:00Z", "b");
+        let base = vec![a.clone(), b.clone()];
+        let edited = Session {
+            note: "a, edited".to_string(),
+            ..a.clone()
+        };
+        // We deleted `a` and they edited it; they deleted `b` we left alone
+        let (kept, edited) = (vec![b], vec![edited]);
+        assert_eq!(merge_sessions(&base, &kept, &edited), edited);
+        assert_eq!(merge_sessions(&base, &edited, &kept), edited);
+    }
+
+    #[test]
+    fn merge_prefers_our_edits_when_both_changed() {
+        let a = noted("2024-05-05T09:00:00Z", "2024-05-05T10:00:00Z", "a");
+        let ours = Session {
+            note: "ours".to_string(),
+            ..a.clone()
+        };
+        let theirs = Session {
+            note: "theirs".to_string(),
+            ..a.clone()
+        };
+        let (base, ours, theirs) = (vec![a], vec![ours], vec![theirs]);
+        assert_eq!(merge_sessions(&base, &ours, &theirs), ours);
+        assert_eq!(merge_sessions(&base, &base, &theirs), theirs);
+    }
+
+    #[test]
+    fn merge_keeps_sessions_added_on_both_sides() {
+        let a = noted("2024-05-05T09:00:00Z", "2024-05-05T10:00:00Z", "a");
+        let ours = noted("2024-05-05T11:00:00Z", "2024-05-05T12:00:00Z", "ours");
+        let theirs = noted("2024-05-05T13:00:00Z", "2024-05-05T14:00:00Z", "theirs");
+        let base = [a.clone()];
+        let merged = merge_sessions(
+            &base,
+            &[a.clone(), ours.clone()],
+            &[a.clone(), theirs.clone()],
+        );
+        assert_eq!(merged, [a.clone(), ours.clone(), theirs]);
+        // The same session added on both sides is kept once
+        let merged = merge_sessions(
+            &base,
+            &[a.clone(), ours.clone()],
+            &[a.clone(), ours.clone()],
+        );
+        assert_eq!(merged, [a, ours]);
+    }
+
+    #[test]
+    fn merge_keeps_sessions_with_the_same_start() {
+        let a = noted("2024-05-05T09:00:00Z", "2024-05-05T10:00:00Z", "a");
+        let imported = noted("2024-05-05T09:00:00Z", "2024-05-05T09:30:00Z", "imported");
+        let other = noted("2024-05-05T09:00:00Z", "2024-05-05T09:45:00Z", "other");
+        let base = vec![a.clone()];
+        let merged = merge_sessions(&base, &[a.clone(), imported.clone()], &base);
+        assert_eq!(merged, [a.clone(), imported.clone()]);
+        // Both sides added a different session at the same start
+        let merged = merge_sessions(
+            &base,
+            &[a.clone(), imported.clone()],
+            &[a.clone(), other.clone()],
+        );
+        assert_eq!(merged.len(), 3);
+        assert!(merged.contains(&imported) && merged.contains(&other));
+    }
+
+    #[test]
+    fn migrates_the_legacy_log_once() {
+        let dir = TempDir::new();
+        let legacy = dir.join("work_log.json");
+        let target = dir.join("data").join("work_log.json");
+        assert!(!migrate_log(&legacy, &target).unwrap());
+
+        fs::write(&legacy, "[]").unwrap();
+        assert!(migrate_log(&legacy, &target).unwrap());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "[]");
+        assert!(!legacy.exists());
+        assert!(dir.join("work_log.json.migrated").exists());
+
+        // A log already at the target is never overwritten
+        fs::write(&legacy, "[{}]").unwrap();
+        assert!(!migrate_log(&legacy, &target).unwrap());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "[]");
+    }
+}
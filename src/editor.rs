@@ -0,0 +1,729 @@
+use crate::calendar::{Calendar, calendar};
+use crate::data::{LogEvent, Session, SessionType, Store};
+use crate::ui::format_duration_str;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+
+const TIME_FORMATS: &[&str] = &["%H:%M", "%H:%M:%S"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Start,
+    End,
+    Type,
+}
+
+/// The start/end/type form of the history editor, for a new session on
+/// `date` or an existing one at `index`.
+#[derive(Debug, Clone)]
+pub struct SessionForm {
+    pub index: Option<usize>,
+    pub date: NaiveDate,
+    pub field: Field,
+    pub start: String,
+    pub end: String,
+    pub kind: SessionType,
+    /// The running session keeps running; only its start and type change.
+    pub running: bool,
+}
+
+impl SessionForm {
+    pub fn insert(date: NaiveDate) -> Self {
+        SessionForm {
+            index: None,
+            date,
+            field: Field::Start,
+            start: String::new(),
+            end: String::new(),
+            kind: SessionType::Work,
+            running: false,
+        }
+    }
+
+    pub fn edit(index: usize, session: &Session) -> Self {
+        let time = |t: DateTime<Utc>| calendar().zone.local_time(t).format("%H:%M").to_string();
+        SessionForm {
+            index: Some(index),
+            date: calendar().date_of(session.start_time),
+            field: Field::Start,
+            start: time(session.start_time),
+            end: session.end_time.map(time).unwrap_or_default(),
+            kind: session.session_type,
+            running: session.end_time.is_none(),
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = match self.field {
+            Field::Start if self.running => Field::Type,
+            Field::Start => Field::End,
+            Field::End => Field::Type,
+            Field::Type => Field::Start,
+        };
+    }
+
+    pub fn previous_field(&mut self) {
+        self.field = match self.field {
+            Field::Start => Field::Type,
+            Field::End => Field::Start,
+            Field::Type if self.running => Field::Start,
+            Field::Type => Field::End,
+        };
+    }
+
+    fn text(&mut self) -> Option<&mut String> {
+        match self.field {
+            Field::Start => Some(&mut self.start),
+            Field::End => Some(&mut self.end),
+            Field::Type => None,
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        if let Some(text) = self.text()
+            && (c.is_ascii_digit() || c == ':')
+        {
+            text.push(c);
+        }
+    }
+
+    pub fn pop(&mut self) {
+        if let Some(text) = self.text() {
+            text.pop();
+        }
+    }
+
+    pub fn cycle_type(&mut self) {
+        self.kind = match self.kind {
+            SessionType::Work => SessionType::Break,
+            SessionType::Break => SessionType::Idle,
+            SessionType::Idle => SessionType::Work,
+        };
+    }
+
+    /// Start and end the form describes, the end `None` while running.
+    fn times(&self, calendar: &Calendar) -> Result<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+        let start = parse_time(calendar, self.date, &self.start).context("start")?;
+        if self.running {
+            return Ok((start, None));
+        }
+        let mut end = parse_time(calendar, self.date, &self.end).context("end")?;
+        // An end before the start is on the next day
+        if end <= start {
+            end = parse_time(calendar, self.date + Duration::days(1), &self.end).context("end")?;
+        }
+        Ok((start, Some(end)))
+    }
+
+    /// The session the form describes, checked against the others.
+    pub fn session(&self, sessions: &[Session]) -> Result<Session> {
+        let (start, end) = self.times(&calendar())?;
+        let mut session = match self.index {
+            Some(index) => sessions[index].clone(),
+            None => Session::new(self.kind, start),
+        };
+        session.uid = Some(session.uid());
+        session.start_time = start;
+        session.end_time = end;
+        session.session_type = self.kind;
+        check(sessions, self.index, &session)?;
+        Ok(session)
+    }
+
+    /// Saves the form, returning where the session ended up and the events.
+    pub fn save(&self, sessions: &mut Vec<Session>) -> Result<(usize, Vec<LogEvent>)> {
+        let session = self.session(sessions)?;
+        Ok(place(sessions, self.index, session))
+    }
+}
+
+/// How the form and the `log` command name a session type.
+pub fn kind_name(kind: SessionType) -> &'static str {
+    match kind {
+        SessionType::Work => "work",
+        SessionType::Break => "break",
+        SessionType::Idle => "idle",
+    }
+}
+
+/// Reads `HH:MM` as a time on the day `date` counts for, so with a day
+/// starting at 04:00 `02:00` is early the next morning.
+fn parse_time(calendar: &Calendar, date: NaiveDate, text: &str) -> Result<DateTime<Utc>> {
+    let time = TIME_FORMATS
+        .iter()
+        .find_map(|f| NaiveTime::parse_from_str(text.trim(), f).ok())
+        .with_context(|| format!("invalid time {text:?}, expected HH:MM"))?;
+    let date = if time < calendar.day_starts_at {
+        date + Duration::days(1)
+    } else {
+        date
+    };
+    calendar
+        .zone
+        .instant_at(&date.and_time(time))
+        .with_context(|| format!("{text} does not exist on {date} (clocks changed)"))
+}
+
+/// Rejects sessions that end before they start, lie in the future or
+/// overlap another work, break or the running session. Like imports, a
+/// finished session may fill in time that was logged as idle; the running
+/// session must not overlap anything so it stays the last one.
+pub fn check(sessions: &[Session], index: Option<usize>, session: &Session) -> Result<()> {
+    let now = Utc::now();
+    let end = session.end_time.unwrap_or(now);
+    if end <= session.start_time {
+        bail!("the session ends before it starts");
+    }
+    if session.start_time > now || end > now {
+        bail!("the session lies in the future");
+    }
+    if end - session.start_time > Duration::days(1) {
+        bail!(
+            "the session would last {}",
+            format_duration_str(end - session.start_time)
+        );
+    }
+    let running = session.end_time.is_none();
+    let clash = sessions
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| Some(*i) != index)
+        .map(|(_, s)| s)
+        .find(|s| {
+            (running || s.session_type != SessionType::Idle || s.end_time.is_none())
+                && s.overlaps(session.start_time, end)
+        });
+    if let Some(other) = clash {
+        let time = |t: DateTime<Utc>| calendar().zone.local_time(t).format("%H:%M").to_string();
+        bail!(
+            "overlaps the {} session {}-{}",
+            kind_name(other.session_type),
+            time(other.start_time),
+            other.end_time.map_or("now".to_string(), time)
+        );
+    }
+    Ok(())
+}
+
+/// Puts `session` in place of the one at `index` (or adds it), keeping the
+/// list sorted by start time. Returns where it went and the events.
+pub fn place(
+    sessions: &mut Vec<Session>,
+    index: Option<usize>,
+    session: Session,
+) -> (usize, Vec<LogEvent>) {
+    let mut events = Vec::new();
+    if let Some(index) = index {
+        let stays = (index == 0 || sessions[index - 1].start_time <= session.start_time)
+            && sessions
+                .get(index + 1)
+                .is_none_or(|next| session.start_time <= next.start_time);
+        if stays {
+            sessions[index] = session.clone();
+            events.push(LogEvent::Replaced { index, session });
+            return (index, events);
+        }
+        sessions.remove(index);
+        events.push(LogEvent::Deleted { index });
+    }
+    let at = sessions.partition_point(|s| s.start_time <= session.start_time);
+    sessions.insert(at, session.clone());
+    events.push(LogEvent::Inserted { index: at, session });
+    (at, events)
+}
+
+/// Splits the session at `index` at the time `text` on its day.
+pub fn split(sessions: &mut Vec<Session>, index: usize, text: &str) -> Result<Vec<LogEvent>> {
+    let start = sessions[index].start_time;
+    let calendar = calendar();
+    let date = calendar.date_of(start);
+    let mut at = parse_time(&calendar, date, text)?;
+    if at <= start {
+        at = parse_time(&calendar, date + Duration::days(1), text)?;
+    }
+    split_at(sessions, index, at).with_context(|| format!("cannot split at {}", text.trim()))
+}
+
+/// Splits the session at `index` into two with the same type, note, project
+/// and tags. A running session keeps running from `at`.
+fn split_at(sessions: &mut Vec<Session>, index: usize, at: DateTime<Utc>) -> Result<Vec<LogEvent>> {
+    let session = sessions[index].clone();
+    let end = session.end_time.unwrap_or_else(Utc::now);
+    if at <= session.start_time || at >= end {
+        bail!("not within the session");
+    }
+    let first = Session {
+        end_time: Some(at),
+        ..session.clone()
+    };
+    // The second part is a new event in exported calendars
+    let second = Session {
+        start_time: at,
+        uid: None,
+        ..session
+    };
+    sessions[index] = first.clone();
+    sessions.insert(index + 1, second.clone());
+    let mut events = vec![LogEvent::Replaced {
+        index,
+        session: first,
+    }];
+    events.push(if second.end_time.is_none() {
+        LogEvent::Started { session: second }
+    } else {
+        LogEvent::Inserted {
+            index: index + 1,
+            session: second,
+        }
+    });
+    Ok(events)
+}
+
+/// Merges the session at `index` with the one after it into one session
+/// spanning both, closing any gap between them. It keeps the first one's
+/// type, unless the next one is running: that stays what it is, so the
+/// timer (and Pomodoro) carry on as before.
+pub fn merge_with_next(sessions: &mut Vec<Session>, index: usize) -> Result<Vec<LogEvent>> {
+    let Some(next) = sessions.get(index + 1).cloned() else {
+        bail!("no later session to merge with");
+    };
+    let first = &sessions[index];
+    let mut tags = first.tags.clone();
+    tags.extend(next.tags.into_iter().filter(|t| !first.tags.contains(t)));
+    let merged = Session {
+        end_time: next.end_time,
+        session_type: if next.end_time.is_none() {
+            next.session_type
+        } else {
+            first.session_type
+        },
+        note: merge_notes(&first.note, &next.note),
+        project: first.project.clone().or(next.project),
+        tags,
+        ..first.clone()
+    };
+    // Checked like a hand-edited session, so a gap of days is not merged
+    let mut others = sessions.clone();
+    others.remove(index + 1);
+    check(&others, Some(index), &merged)?;
+    sessions[index] = merged.clone();
+    sessions.remove(index + 1);
+    Ok(vec![
+        LogEvent::Replaced {
+            index,
+            session: merged,
+        },
+        LogEvent::Deleted { index: index + 1 },
+    ])
+}
+
+/// Keeps both notes unless they are the same or one is empty.
+fn merge_notes(first: &str, second: &str) -> String {
+    if second.is_empty() || first == second {
+        first.to_string()
+    } else if first.is_empty() {
+        second.to_string()
+    } else {
+        format!("{first}; {second}")
+    }
+}
+
+/// Options of the `log` command.
+#[derive(Debug)]
+pub struct LogOptions {
+    pub date: Option<NaiveDate>,
+    pub start: String,
+    pub end: String,
+    pub kind: SessionType,
+    pub note: Option<String>,
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// `log` command: adds a finished session, checked like the TUI form.
+pub fn run_log(mut store: Box<dyn Store>, options: LogOptions) -> Result<()> {
+    let mut sessions = store.load()?.sessions;
+    let form = SessionForm {
+        start: options.start,
+        end: options.end,
+        kind: options.kind,
+        ..SessionForm::insert(options.date.unwrap_or_else(|| calendar().today()))
+    };
+    let mut session = form.session(&sessions)?;
+    session.note = options.note.unwrap_or_default();
+    session.project = options.project;
+    session.tags = options.tags;
+    let line = format!(
+        "Logged {} {} on {} ({}-{})",
+        format_duration_str(session.duration()),
+        kind_name(session.session_type),
+        form.date,
+        form.start,
+        form.end
+    );
+    let (_, events) = place(&mut sessions, None, session);
+    store.record(&sessions, &events)?;
+    println!("{line}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{date, logged, replays_to, session, utc, utc_calendar};
+
+    fn form(start: &str, end: &str) -> SessionForm {
+        SessionForm {
+            start: start.to_string(),
+            end: end.to_string(),
+            ..SessionForm::insert(date("2024-05-05"))
+        }
+    }
+
+    #[test]
+    fn end_before_start_is_the_next_day() {
+        let calendar = utc_calendar();
+        assert_eq!(
+            form("09:00", "10:30").times(&calendar).unwrap(),
+            (
+                utc("2024-05-05T09:00:00Z"),
+                Some(utc("2024-05-05T10:30:00Z"))
+            )
+        );
+        assert_eq!(
+            form("22:00", "01:15").times(&calendar).unwrap(),
+            (
+                utc("2024-05-05T22:00:00Z"),
+                Some(utc("2024-05-06T01:15:00Z"))
+            )
+        );
+        assert!(form("9am", "10:00").times(&calendar).is_err());
+    }
+
+    #[test]
+    fn times_before_the_day_start_are_the_next_morning() {
+        let calendar = utc_calendar().with_day_start(NaiveTime::from_hms_opt(4, 0, 0).unwrap());
+        assert_eq!(
+            form("02:00", "03:30").times(&calendar).unwrap(),
+            (
+                utc("2024-05-06T02:00:00Z"),
+                Some(utc("2024-05-06T03:30:00Z"))
+            )
+        );
+        assert_eq!(
+            form("23:00", "03:00").times(&calendar).unwrap(),
+            (
+                utc("2024-05-05T23:00:00Z"),
+                Some(utc("2024-05-06T03:00:00Z"))
+            )
+        );
+    }
+
+    #[test]
+    fn overlaps_are_rejected_but_idle_time_can_be_filled() {
+        let sessions = vec![
+            session(
+                SessionType::Work,
+                "2024-05-05T09:00:00Z",
+                Some("2024-05-05T10:00:00Z"),
+            ),
+            session(
+                SessionType::Idle,
+                "2024-05-05T10:00:00Z",
+                Some("2024-05-05T12:00:00Z"),
+            ),
+        ];
+        let fill = session(
+            SessionType::Work,
+            "2024-05-05T10:30:00Z",
+            Some("2024-05-05T11:00:00Z"),
+        );
+        assert!(check(&sessions, None, &fill).is_ok());
+        let clash = session(
+            SessionType::Break,
+            "2024-05-05T09:45:00Z",
+            Some("2024-05-05T10:15:00Z"),
+        );
+        let err = check(&sessions, None, &clash).unwrap_err();
+        assert!(err.to_string().contains("overlaps the work session"));
+        // A session does not clash with its own old times
+        assert!(check(&sessions, Some(0), &clash).is_ok());
+        let backwards = session(
+            SessionType::Work,
+            "2024-05-05T13:00:00Z",
+            Some("2024-05-05T12:30:00Z"),
+        );
+        assert!(check(&sessions, None, &backwards).is_err());
+    }
+
+    #[test]
+    fn place_keeps_the_list_sorted() {
+        let mut sessions = vec![
+            session(
+                SessionType::Work,
+                "2024-05-05T09:00:00Z",
+                Some("2024-05-05T10:00:00Z"),
+            ),
+            session(
+                SessionType::Break,
+                "2024-05-05T10:00:00Z",
+                Some("2024-05-05T10:15:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-05T11:00:00Z",
+                Some("2024-05-05T12:00:00Z"),
+            ),
+        ];
+
+        // Still between its neighbours: replaced in place
+        let shorter = session(
+            SessionType::Break,
+            "2024-05-05T10:05:00Z",
+            Some("2024-05-05T10:15:00Z"),
+        );
+        let (at, events) = place(&mut sessions, Some(1), shorter.clone());
+        assert_eq!(at, 1);
+        assert!(matches!(
+            events.as_slice(),
+            [LogEvent::Replaced { index: 1, .. }]
+        ));
+        assert_eq!(sessions[1], shorter);
+
+        // Moved past the next one: deleted and inserted after it
+        let later = session(
+            SessionType::Break,
+            "2024-05-05T12:00:00Z",
+            Some("2024-05-05T12:15:00Z"),
+        );
+        let (at, events) = place(&mut sessions, Some(1), later.clone());
+        assert_eq!(at, 2);
+        assert!(matches!(
+            events.as_slice(),
+            [
+                LogEvent::Deleted { index: 1 },
+                LogEvent::Inserted { index: 2, .. }
+            ]
+        ));
+        assert_eq!(sessions[2], later);
+
+        // A new session goes where its start belongs
+        let early = session(
+            SessionType::Work,
+            "2024-05-05T08:00:00Z",
+            Some("2024-05-05T08:30:00Z"),
+        );
+        let (at, events) = place(&mut sessions, None, early);
+        assert_eq!(at, 0);
+        assert!(matches!(
+            events.as_slice(),
+            [LogEvent::Inserted { index: 0, .. }]
+        ));
+        assert!(sessions.is_sorted_by_key(|s| s.start_time));
+
+        // The events replay to the same list
+        let before = vec![
+            session(
+                SessionType::Work,
+                "2024-05-05T09:00:00Z",
+                Some("2024-05-05T10:00:00Z"),
+            ),
+            session(
+                SessionType::Break,
+                "2024-05-05T10:00:00Z",
+                Some("2024-05-05T10:15:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-05T11:00:00Z",
+                Some("2024-05-05T12:00:00Z"),
+            ),
+        ];
+        let mut list = before.clone();
+        let (_, events) = place(
+            &mut list,
+            Some(0),
+            session(
+                SessionType::Work,
+                "2024-05-05T10:30:00Z",
+                Some("2024-05-05T10:45:00Z"),
+            ),
+        );
+        replays_to(&before, &events, &list);
+    }
+
+    #[test]
+    fn moving_the_start_keeps_the_exported_event() {
+        let sessions = vec![logged(
+            SessionType::Work,
+            "2024-05-06",
+            "09:00:00",
+            "10:00:00",
+        )];
+        let mut form = SessionForm::edit(0, &sessions[0]);
+        form.start = "08:30".to_string();
+        let moved = form.session(&sessions).unwrap();
+        assert_eq!(
+            moved.start_time,
+            sessions[0].start_time - Duration::minutes(30)
+        );
+        assert_eq!(moved.uid(), sessions[0].uid());
+
+        let moved = vec![moved];
+        let mut form = SessionForm::edit(0, &moved[0]);
+        form.start = "09:15".to_string();
+        assert_eq!(form.session(&moved).unwrap().uid(), sessions[0].uid());
+    }
+
+    #[test]
+    fn splitting_keeps_note_project_and_tags() {
+        let mut work = session(
+            SessionType::Work,
+            "2024-05-05T09:00:00Z",
+            Some("2024-05-05T17:00:00Z"),
+        );
+        work.note = "api".to_string();
+        work.project = Some("acme".to_string());
+        work.tags = vec!["x".to_string()];
+        let before = vec![work.clone()];
+        let mut sessions = before.clone();
+        let events = split_at(&mut sessions, 0, utc("2024-05-05T12:00:00Z")).unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                LogEvent::Replaced { index: 0, .. },
+                LogEvent::Inserted { index: 1, .. }
+            ]
+        ));
+        assert_eq!(sessions[0].end_time, Some(utc("2024-05-05T12:00:00Z")));
+        assert_eq!(sessions[1].start_time, utc("2024-05-05T12:00:00Z"));
+        assert_eq!(sessions[1].end_time, work.end_time);
+        // The first half stays the exported event, the second is a new one
+        assert_eq!(sessions[0].uid(), work.uid());
+        assert_ne!(sessions[1].uid(), work.uid());
+        for half in &sessions {
+            assert_eq!(
+                (&half.note, &half.project, &half.tags),
+                (&work.note, &work.project, &work.tags)
+            );
+        }
+        replays_to(&before, &events, &sessions);
+
+        assert!(split_at(&mut sessions, 0, utc("2024-05-05T09:00:00Z")).is_err());
+        assert!(split_at(&mut sessions, 0, utc("2024-05-05T13:00:00Z")).is_err());
+    }
+
+    #[test]
+    fn splitting_the_running_session_keeps_it_running() {
+        let start = Utc::now() - Duration::hours(2);
+        let at = Utc::now() - Duration::hours(1);
+        let before = vec![Session {
+            start_time: start,
+            ..session(SessionType::Work, "2024-05-05T09:00:00Z", None)
+        }];
+        let mut sessions = before.clone();
+        let events = split_at(&mut sessions, 0, at).unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                LogEvent::Replaced { index: 0, .. },
+                LogEvent::Started { .. }
+            ]
+        ));
+        assert_eq!(sessions[0].end_time, Some(at));
+        assert_eq!(sessions[1].start_time, at);
+        assert_eq!(sessions[1].end_time, None);
+        replays_to(&before, &events, &sessions);
+        assert!(split_at(&mut sessions, 1, Utc::now() + Duration::minutes(5)).is_err());
+    }
+
+    #[test]
+    fn merging_combines_notes_and_tags() {
+        let mut first = session(
+            SessionType::Work,
+            "2024-05-05T09:00:00Z",
+            Some("2024-05-05T10:00:00Z"),
+        );
+        first.note = "api".to_string();
+        first.tags = vec!["x".to_string(), "y".to_string()];
+        let mut second = session(
+            SessionType::Break,
+            "2024-05-05T10:00:00Z",
+            Some("2024-05-05T10:00:05Z"),
+        );
+        second.project = Some("acme".to_string());
+        let mut third = session(
+            SessionType::Work,
+            "2024-05-05T10:10:00Z",
+            Some("2024-05-05T11:00:00Z"),
+        );
+        third.note = "tests".to_string();
+        third.tags = vec!["y".to_string(), "z".to_string()];
+        let before = vec![first, second, third];
+
+        let mut sessions = before.clone();
+        let mut events = merge_with_next(&mut sessions, 0).unwrap();
+        events.extend(merge_with_next(&mut sessions, 0).unwrap());
+        assert_eq!(sessions.len(), 1);
+        let merged = &sessions[0];
+        assert_eq!(merged.session_type, SessionType::Work);
+        assert_eq!(merged.start_time, utc("2024-05-05T09:00:00Z"));
+        assert_eq!(merged.end_time, Some(utc("2024-05-05T11:00:00Z")));
+        assert_eq!(merged.note, "api; tests");
+        assert_eq!(merged.project.as_deref(), Some("acme"));
+        assert_eq!(merged.tags, ["x", "y", "z"]);
+        replays_to(&before, &events, &sessions);
+
+        assert!(merge_with_next(&mut sessions, 0).is_err());
+        assert_eq!(merge_notes("api", "api"), "api");
+        assert_eq!(merge_notes("", "tests"), "tests");
+    }
+
+    #[test]
+    fn merging_into_the_running_session_keeps_it_running() {
+        let now = Utc::now();
+        let before = vec![
+            Session {
+                start_time: now - Duration::hours(2),
+                end_time: Some(now - Duration::hours(1)),
+                ..session(SessionType::Break, "2024-05-05T09:00:00Z", None)
+            },
+            Session {
+                start_time: now - Duration::hours(1),
+                ..session(SessionType::Work, "2024-05-05T09:00:00Z", None)
+            },
+        ];
+        let mut sessions = before.clone();
+        let events = merge_with_next(&mut sessions, 0).unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                LogEvent::Replaced { index: 0, .. },
+                LogEvent::Deleted { index: 1 }
+            ]
+        ));
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].start_time, now - Duration::hours(2));
+        assert_eq!(sessions[0].end_time, None);
+        assert_eq!(sessions[0].session_type, SessionType::Work);
+        replays_to(&before, &events, &sessions);
+    }
+
+    #[test]
+    fn merging_does_not_bridge_days() {
+        let mut sessions = vec![
+            session(
+                SessionType::Work,
+                "2024-05-05T09:00:00Z",
+                Some("2024-05-05T10:00:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-07T09:00:00Z",
+                Some("2024-05-07T10:00:00Z"),
+            ),
+        ];
+        assert!(merge_with_next(&mut sessions, 0).is_err());
+        assert_eq!(sessions.len(), 2);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::calendar::calendar;
+use crate::data::{Session, SessionType, Store, local_day_bounds};
+use crate::ui::format_duration_str;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Ics,
+}
+
+/// Which sessions to export and how.
+#[derive(Debug)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// First and last local day to include; open-ended when `None`.
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub utc: bool,
+    pub include_idle: bool,
+    /// Written to stdout when `None`.
+    pub output: Option<PathBuf>,
+}
+
+impl ExportOptions {
+    /// Options for exporting a single day from the TUI.
+    pub fn for_day(format: ExportFormat, date: NaiveDate) -> Self {
+        ExportOptions {
+            format,
+            from: Some(date),
+            to: Some(date),
+            utc: false,
+            include_idle: false,
+            output: None,
+        }
+    }
+
+    fn bounds(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let from = self
+            .from
+            .map_or(DateTime::UNIX_EPOCH, |d| local_day_bounds(d).0);
+        let to = self
+            .to
+            .map_or_else(|| Utc::now() + Duration::days(1), |d| local_day_bounds(d).1);
+        (from, to)
+    }
+
+    /// Sessions that start within the range, so one running past midnight is
+    /// exported once.
+    pub fn select<'a>(&self, sessions: impl IntoIterator<Item = &'a Session>) -> Vec<&'a Session> {
+        let (from, to) = self.bounds();
+        sessions
+            .into_iter()
+            .filter(|s| s.start_time >= from && s.start_time < to)
+            .filter(|s| {
+                s.session_type != SessionType::Idle
+                    || (self.include_idle && self.format != ExportFormat::Ics)
+            })
+            .collect()
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W, sessions: &[&Session]) -> Result<()> {
+        match self.format {
+            ExportFormat::Csv => write_csv(out, sessions, self.utc),
+            ExportFormat::Ics => write_ics(out, sessions),
+        }
+    }
+}
+
+/// `export` command: reads the range from the store and writes it out.
+pub fn run(store: &dyn Store, options: &ExportOptions) -> Result<()> {
+    let (from, to) = options.bounds();
+    let stored = store.sessions_between(from, to)?;
+    let sessions = options.select(&stored);
+    match &options.output {
+        Some(path) => {
+            let mut out = BufWriter::new(
+                File::create(path).with_context(|| format!("creating {}", path.display()))?,
+            );
+            options.write(&mut out, &sessions)?;
+            out.flush()?;
+            eprintln!("Exported {} sessions to {}", sessions.len(), path.display());
+        }
+        None => options.write(&mut io::stdout().lock(), &sessions)?,
+    }
+    Ok(())
+}
+
+/// Writes `sessions` to `<dir>/<date>.<ext>` and returns the path.
+pub fn export_to_dir(
+    dir: &Path,
+    options: &ExportOptions,
+    sessions: &[&Session],
+) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let name = match (options.from, options.to) {
+        (Some(from), Some(to)) if from == to => from.format("%Y-%m-%d").to_string(),
+        (Some(from), Some(to)) => format!("{}_{}", from.format("%Y-%m-%d"), to.format("%Y-%m-%d")),
+        _ => "all".to_string(),
+    };
+    let ext = match options.format {
+        ExportFormat::Csv => "csv",
+        ExportFormat::Ics => "ics",
+    };
+    let path = dir.join(format!("{name}.{ext}"));
+    let mut out = BufWriter::new(File::create(&path)?);
+    options.write(&mut out, sessions)?;
+    out.flush()?;
+    Ok(path)
+}
+
+/// RFC 4180 CSV with CRLF line endings, quoted the way `import` reads it.
+fn write_csv<W: Write>(out: &mut W, sessions: &[&Session], utc: bool) -> Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(out);
+    let zone = if utc { "utc" } else { "local" };
+    let (start, end) = (format!("start_{zone}"), format!("end_{zone}"));
+    writer.write_record([
+        &start, &end, "type", "duration", "note", "project", "tags", "timezone",
+    ])?;
+    let format_time = |t: DateTime<Utc>| {
+        let fmt = "%Y-%m-%d %H:%M:%S";
+        if utc {
+            t.format(fmt).to_string()
+        } else {
+            calendar().zone.local_time(t).format(fmt).to_string()
+        }
+    };
+    for s in sessions {
+        writer.write_record([
+            format_time(s.start_time),
+            s.end_time.map(format_time).unwrap_or_default(),
+            format!("{:?}", s.session_type),
+            format_duration_str(s.duration()),
+            s.note.clone(),
+            s.project.clone().unwrap_or_default(),
+            s.tags.join(", "),
+            s.zone.clone().unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Escapes a TEXT value (RFC 5545 3.3.11).
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Writes a content line, folded so no line exceeds 75 octets. Folds fall
+/// between characters, and the text between them is written in one go.
+fn ics_line<W: Write>(out: &mut W, line: &str) -> Result<()> {
+    let mut from = 0;
+    // Continuation lines start with a space
+    let mut room = 75;
+    for (i, c) in line.char_indices() {
+        if i + c.len_utf8() - from > room {
+            out.write_all(&line.as_bytes()[from..i])?;
+            out.write_all(b"\r\n ")?;
+            from = i;
+            room = 74;
+        }
+    }
+    out.write_all(&line.as_bytes()[from..])?;
+    out.write_all(b"\r\n")?;
+    Ok(())
+}
+
+fn ics_time(t: DateTime<Utc>) -> String {
+    t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// One VEVENT per session. The UID stays the same when the session is
+/// edited, so re-exporting updates events in the calendar instead of
+/// duplicating them.
+fn write_ics<W: Write>(out: &mut W, sessions: &[&Session]) -> Result<()> {
+    let now = Utc::now();
+    ics_line(out, "BEGIN:VCALENDAR")?;
+    ics_line(out, "VERSION:2.0")?;
+    ics_line(out, "PRODID:-//rust_pet_timer//EN")?;
+    for s in sessions {
+        let summary = if s.note.is_empty() {
+            format!("{:?}", s.session_type)
+        } else {
+            s.note.clone()
+        };
+        ics_line(out, "BEGIN:VEVENT")?;
+        ics_line(out, &format!("UID:{}@rust_pet_timer", s.uid()))?;
+        ics_line(out, &format!("DTSTAMP:{}", ics_time(now)))?;
+        ics_line(out, &format!("DTSTART:{}", ics_time(s.start_time)))?;
+        ics_line(
+            out,
+            &format!("DTEND:{}", ics_time(s.end_time.unwrap_or(now))),
+        )?;
+        ics_line(out, &format!("SUMMARY:{}", ics_text(&summary)))?;
+        if !s.note.is_empty() {
+            ics_line(out, &format!("DESCRIPTION:{}", ics_text(&s.note)))?;
+        }
+        ics_line(out, &format!("CATEGORIES:{:?}", s.session_type))?;
+        ics_line(out, "END:VEVENT")?;
+    }
+    ics_line(out, "END:VCALENDAR")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::session;
+
+    fn work(note: &str) -> Session {
+        Session {
+            note: note.to_string(),
+            project: Some("acme".to_string()),
+            tags: vec!["a".to_string(), "b".to_string()],
+            ..session(
+                SessionType::Work,
+                "2026-10-14T09:00:00Z",
+                Some("2026-10-14T10:30:00Z"),
+            )
+        }
+    }
+
+    #[test]
+    fn csv_round_trips_awkward_notes() {
+        let note = "fix, then \"ship\"\nand review";
+        let mut out = Vec::new();
+        write_csv(&mut out, &[&work(note)], true).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("start_utc,end_utc,type,duration,note,project,tags,timezone\r\n"));
+
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(&rows[0][0], "2026-10-14 09:00:00");
+        assert_eq!(&rows[0][3], "01:30:00");
+        assert_eq!(&rows[0][4], note);
+        assert_eq!(&rows[0][6], "a, b");
+    }
+
+    #[test]
+    fn ics_lines_fold_between_characters() {
+        let line = format!("SUMMARY:{}", "Überstunden für das Café ☕ ".repeat(8));
+        let mut out = Vec::new();
+        ics_line(&mut out, &line).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let physical: Vec<&str> = text.trim_end_matches("\r\n").split("\r\n").collect();
+        assert!(physical.len() > 1);
+        assert!(physical.iter().all(|l| l.len() <= 75));
+        assert!(physical[1..].iter().all(|l| l.starts_with(' ')));
+        let unfolded: String = physical
+            .iter()
+            .enumerate()
+            .map(|(i, l)| if i == 0 { *l } else { &l[1..] })
+            .collect();
+        assert_eq!(unfolded, line);
+    }
+
+    #[test]
+    fn ics_events_carry_times_and_an_escaped_summary() {
+        let mut running = session(SessionType::Break, "2026-10-14T10:30:00Z", None);
+        running.uid = Some("42".to_string());
+        let finished = work("plan; review, ship\\now\nthen rest");
+        let mut out = Vec::new();
+        write_ics(&mut out, &[&finished, &running]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.ends_with("END:VCALENDAR\r\n"));
+        let lines: Vec<&str> = text.trim_end_matches("\r\n").split("\r\n").collect();
+        assert!(lines.iter().all(|l| !l.contains(['\r', '\n'])));
+        let events: Vec<&[&str]> = lines.split(|l| *l == "BEGIN:VEVENT").skip(1).collect();
+        assert_eq!(events.len(), 2);
+
+        let field = |event: &[&str], name: &str| {
+            event
+                .iter()
+                .find_map(|l| l.strip_prefix(name)?.strip_prefix(':'))
+                .map(str::to_string)
+        };
+        let first = events[0];
+        assert_eq!(
+            field(first, "UID").unwrap(),
+            format!("{}@rust_pet_timer", finished.uid())
+        );
+        assert_eq!(field(first, "DTSTART").unwrap(), "20261014T090000Z");
+        assert_eq!(field(first, "DTEND").unwrap(), "20261014T103000Z");
+        assert_eq!(
+            field(first, "SUMMARY").unwrap(),
+            "plan\\; review\\, ship\\\\now\\nthen rest"
+        );
+        assert_eq!(field(first, "CATEGORIES").unwrap(), "Work");
+
+        // Without a note the type is the summary; a running session ends now
+        let second = events[1];
+        assert_eq!(field(second, "UID").unwrap(), "42@rust_pet_timer");
+        assert_eq!(field(second, "SUMMARY").unwrap(), "Break");
+        assert_eq!(field(second, "DESCRIPTION"), None);
+        assert_eq!(field(second, "DTEND"), field(second, "DTSTAMP"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::calendar::calendar;
+use crate::data::{Session, Timeline};
+use crate::report::day_totals;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::Deserialize;
+
+/// Daily work targets in hours. `hours` applies to every day a weekday does
+/// not override; a target of 0 makes that day a day off.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct GoalConfig {
+    pub hours: Option<f64>,
+    pub mon: Option<f64>,
+    pub tue: Option<f64>,
+    pub wed: Option<f64>,
+    pub thu: Option<f64>,
+    pub fri: Option<f64>,
+    pub sat: Option<f64>,
+    pub sun: Option<f64>,
+}
+
+impl GoalConfig {
+    /// Work target for `date`, or `None` if it has none.
+    pub fn for_date(&self, date: NaiveDate) -> Option<Duration> {
+        let hours = match date.weekday() {
+            Weekday::Mon => self.mon,
+            Weekday::Tue => self.tue,
+            Weekday::Wed => self.wed,
+            Weekday::Thu => self.thu,
+            Weekday::Fri => self.fri,
+            Weekday::Sat => self.sat,
+            Weekday::Sun => self.sun,
+        }
+        .or(self.hours)?;
+        (hours > 0.0).then(|| Duration::seconds((hours * 3600.0) as i64))
+    }
+
+    pub fn is_set(&self) -> bool {
+        [
+            self.hours, self.mon, self.tue, self.wed, self.thu, self.fri, self.sat, self.sun,
+        ]
+        .iter()
+        .any(|h| h.is_some_and(|h| h > 0.0))
+    }
+
+    /// Whether `work` meets the target of `date`; `None` on days off.
+    pub fn met(&self, date: NaiveDate, work: Duration) -> Option<bool> {
+        self.for_date(date).map(|goal| work >= goal)
+    }
+
+    /// Consecutive workdays before `today` on which the goal was met. Days
+    /// off neither count nor break the streak. Today is up to the caller: it
+    /// only counts once met, so an unfinished day does not reset it.
+    pub fn streak_before(
+        &self,
+        sessions: &[Session],
+        timeline: &Timeline,
+        today: NaiveDate,
+    ) -> u32 {
+        let Some(first) = sessions.first().map(|s| calendar().date_of(s.start_time)) else {
+            return 0;
+        };
+        self.streak_between(first, today - Duration::days(1), |date| {
+            day_totals(sessions, timeline, date, date)[0].work
+        })
+    }
+
+    /// Adds today, worked for `work` so far, to the streak before it.
+    pub fn streak_with_today(&self, before: u32, today: NaiveDate, work: Duration) -> u32 {
+        before + u32::from(self.met(today, work) == Some(true))
+    }
+
+    /// Streak ending on `last`, walking back no further than `first`.
+    fn streak_between(
+        &self,
+        first: NaiveDate,
+        last: NaiveDate,
+        work_on: impl Fn(NaiveDate) -> Duration,
+    ) -> u32 {
+        if !self.is_set() {
+            return 0;
+        }
+        let mut streak = 0;
+        let mut date = last;
+        while date >= first {
+            match self.met(date, work_on(date)) {
+                Some(true) => streak += 1,
+                Some(false) => break,
+                None => {}
+            }
+            date -= Duration::days(1);
+        }
+        streak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::date;
+
+    fn weekdays(hours: f64) -> GoalConfig {
+        GoalConfig {
+            hours: Some(hours),
+            sat: Some(0.0),
+            sun: Some(0.0),
+            ..GoalConfig::default()
+        }
+    }
+
+    #[test]
+    fn weekdays_override_the_daily_target() {
+        let goals = GoalConfig {
+            fri: Some(4.0),
+            ..weekdays(8.0)
+        };
+        // 2024-05-06 is a Monday
+        assert_eq!(goals.for_date(date("2024-05-06")), Some(Duration::hours(8)));
+        assert_eq!(goals.for_date(date("2024-05-10")), Some(Duration::hours(4)));
+        assert_eq!(goals.for_date(date("2024-05-11")), None);
+        let fridays_only = GoalConfig {
+            fri: Some(1.5),
+            ..GoalConfig::default()
+        };
+        assert_eq!(fridays_only.for_date(date("2024-05-06")), None);
+        assert_eq!(
+            fridays_only.for_date(date("2024-05-10")),
+            Some(Duration::minutes(90))
+        );
+        assert!(!GoalConfig::default().is_set());
+    }
+
+    #[test]
+    fn days_off_neither_count_nor_break_the_streak() {
+        let goals = weekdays(2.0);
+        let short = date("2024-05-02");
+        let work_on = |day: NaiveDate| {
+            if day == short {
+                Duration::hours(1)
+            } else if day.weekday().number_from_monday() >= 6 {
+                Duration::zero()
+            } else {
+                Duration::hours(3)
+            }
+        };
+        // Fri 3rd, Mon 6th and Tue 7th, across a weekend without work
+        assert_eq!(
+            goals.streak_between(date("2024-04-01"), date("2024-05-07"), work_on),
+            3
+        );
+        // Never further back than the first day of the log
+        assert_eq!(
+            goals.streak_between(date("2024-05-06"), date("2024-05-07"), work_on),
+            2
+        );
+        // An unfinished day counts once met and does not reset the streak
+        let monday = date("2024-05-06");
+        assert_eq!(goals.streak_with_today(3, monday, Duration::hours(1)), 3);
+        assert_eq!(goals.streak_with_today(3, monday, Duration::hours(2)), 4);
+        assert_eq!(
+            goals.streak_with_today(3, date("2024-05-11"), Duration::hours(5)),
+            3
+        );
+        assert_eq!(
+            GoalConfig::default().streak_between(date("2024-05-06"), date("2024-05-07"), work_on),
+            0
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use crate::data::{LogEvent, Session, SessionType};
+use crate::reminders::shell_command;
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Instant,
+};
+
+/// How often the idle probe runs.
+const PROBE_EVERY: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct IdleConfig {
+    /// Away this long counts as idle; detection is off without it or at 0.
+    pub minutes: Option<i64>,
+    /// Command printing how long the desktop has been idle in milliseconds,
+    /// e.g. `xprintidle`. Without it, time the terminal is unfocused counts.
+    pub probe: Option<String>,
+}
+
+/// A stretch of time the user was away from the keyboard.
+#[derive(Debug, Clone, Copy)]
+pub struct Away {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+}
+
+/// Notices when the user walks away, from the idle probe if there is one,
+/// else from the terminal losing focus.
+pub struct IdleWatch {
+    config: IdleConfig,
+    focus_lost: Option<DateTime<Utc>>,
+    /// Start of the idle time the probe is currently reporting.
+    idle_since: Option<DateTime<Utc>>,
+    last_probe: Option<Instant>,
+    /// Result of the probe running in the background, so a slow probe
+    /// does not hold up the UI.
+    pending: Option<Receiver<Result<Duration>>>,
+}
+
+impl IdleWatch {
+    pub fn new(config: IdleConfig) -> Self {
+        IdleWatch {
+            config,
+            focus_lost: None,
+            idle_since: None,
+            last_probe: None,
+            pending: None,
+        }
+    }
+
+    fn threshold(&self) -> Option<Duration> {
+        self.config
+            .minutes
+            .filter(|m| *m > 0)
+            .map(Duration::minutes)
+    }
+
+    fn away(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Option<Away> {
+        let threshold = self.threshold()?;
+        (until - since >= threshold).then_some(Away { since, until })
+    }
+
+    pub fn focus_lost(&mut self, now: DateTime<Utc>) {
+        self.focus_lost = Some(now);
+    }
+
+    /// Returns the time away if the terminal was unfocused long enough.
+    pub fn focus_gained(&mut self, now: DateTime<Utc>) -> Option<Away> {
+        let since = self.focus_lost.take()?;
+        if self.config.probe.is_some() {
+            return None;
+        }
+        self.away(since, now)
+    }
+
+    /// Runs the probe every few seconds on its own thread. Returns the time
+    /// away once the user is back after being idle long enough.
+    pub fn poll(&mut self, now: DateTime<Utc>) -> Result<Option<Away>> {
+        let (Some(threshold), Some(probe)) = (self.threshold(), &self.config.probe) else {
+            return Ok(None);
+        };
+        let Some(pending) = &self.pending else {
+            if self
+                .last_probe
+                .is_none_or(|last| last.elapsed() >= PROBE_EVERY)
+            {
+                self.last_probe = Some(Instant::now());
+                let (tx, rx) = mpsc::channel();
+                let probe = probe.clone();
+                thread::spawn(move || tx.send(run_probe(&probe)));
+                self.pending = Some(rx);
+            }
+            return Ok(None);
+        };
+        let result = match pending.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return Ok(None),
+            Err(TryRecvError::Disconnected) => Err(anyhow!("idle probe {probe:?} stopped")),
+        };
+        self.pending = None;
+        let idle = match result {
+            Ok(idle) => idle,
+            Err(err) => {
+                // Fall back to focus events rather than failing every time
+                self.config.probe = None;
+                return Err(err);
+            }
+        };
+        if idle >= threshold {
+            self.idle_since.get_or_insert(now - idle);
+            return Ok(None);
+        }
+        Ok(self
+            .idle_since
+            .take()
+            .and_then(|since| self.away(since, now - idle)))
+    }
+}
+
+/// Splits the running work session at `index` around the time `away`,
+/// turning it into a `kind` session (a break, or idle to discard it), and
+/// carries on working from the moment the user came back.
+pub fn split_work(
+    sessions: &mut Vec<Session>,
+    index: usize,
+    away: Away,
+    kind: SessionType,
+    now: DateTime<Utc>,
+) -> Vec<LogEvent> {
+    let work = sessions[index].clone();
+    let since = away.since.max(work.start_time);
+    let until = away.until.min(now).max(since);
+    let mut events = Vec::new();
+    if since > work.start_time {
+        sessions[index].end_time = Some(since);
+        events.push(LogEvent::Ended {
+            index,
+            end_time: since,
+        });
+    } else {
+        // Away from the very start: nothing of it was work
+        sessions.remove(index);
+        events.push(LogEvent::Deleted { index });
+    }
+    let gap = Session {
+        end_time: Some(until),
+        ..Session::new(kind, since)
+    };
+    sessions.push(gap.clone());
+    events.push(LogEvent::Inserted {
+        index: sessions.len() - 1,
+        session: gap,
+    });
+    let resumed = Session {
+        note: work.note,
+        project: work.project,
+        tags: work.tags,
+        ..Session::new(work.session_type, until)
+    };
+    sessions.push(resumed.clone());
+    events.push(LogEvent::Started { session: resumed });
+    events
+}
+
+fn run_probe(probe: &str) -> Result<Duration> {
+    let output = shell_command(probe)
+        .output()
+        .with_context(|| format!("running idle probe {probe:?}"))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let millis: i64 = text
+        .trim()
+        .parse()
+        .with_context(|| format!("idle probe {probe:?} printed {:?}", text.trim()))?;
+    Ok(Duration::milliseconds(millis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{replays_to, session, utc};
+
+    fn running_work(start: &str) -> Session {
+        Session {
+            note: "api".to_string(),
+            project: Some("acme".to_string()),
+            ..session(SessionType::Work, start, None)
+        }
+    }
+
+    #[test]
+    fn time_away_splits_the_work_session() {
+        let before = vec![running_work("2024-05-05T09:00:00Z")];
+        let mut sessions = before.clone();
+        let away = Away {
+            since: utc("2024-05-05T10:00:00Z"),
+            until: utc("2024-05-05T10:30:00Z"),
+        };
+        let events = split_work(
+            &mut sessions,
+            0,
+            away,
+            SessionType::Break,
+            utc("2024-05-05T10:31:00Z"),
+        );
+        assert!(matches!(
+            events.as_slice(),
+            [
+                LogEvent::Ended { index: 0, .. },
+                LogEvent::Inserted { index: 1, .. },
+                LogEvent::Started { .. }
+            ]
+        ));
+        assert_eq!(sessions[0].end_time, Some(away.since));
+        assert_eq!(sessions[1].session_type, SessionType::Break);
+        assert_eq!(
+            (sessions[1].start_time, sessions[1].end_time),
+            (away.since, Some(away.until))
+        );
+        assert_eq!(sessions[2].start_time, away.until);
+        assert_eq!(sessions[2].note, "api");
+        replays_to(&before, &events, &sessions);
+    }
+
+    #[test]
+    fn away_from_the_start_leaves_no_work() {
+        let before = vec![running_work("2024-05-05T09:00:00Z")];
+        let mut sessions = before.clone();
+        let away = Away {
+            since: utc("2024-05-05T08:00:00Z"),
+            until: utc("2024-05-05T09:30:00Z"),
+        };
+        let events = split_work(
+            &mut sessions,
+            0,
+            away,
+            SessionType::Idle,
+            utc("2024-05-05T09:31:00Z"),
+        );
+        assert!(matches!(
+            events.as_slice(),
+            [
+                LogEvent::Deleted { index: 0 },
+                LogEvent::Inserted { index: 0, .. },
+                LogEvent::Started { .. }
+            ]
+        ));
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].session_type, SessionType::Idle);
+        assert_eq!(sessions[0].start_time, before[0].start_time);
+        assert_eq!(sessions[0].end_time, Some(away.until));
+        assert_eq!(sessions[1].start_time, away.until);
+        assert_eq!(sessions[1].project.as_deref(), Some("acme"));
+        replays_to(&before, &events, &sessions);
+    }
+
+    #[test]
+    fn time_away_is_clamped_to_now() {
+        let mut sessions = vec![running_work("2024-05-05T09:00:00Z")];
+        let now = utc("2024-05-05T10:00:00Z");
+        let away = Away {
+            since: utc("2024-05-05T09:30:00Z"),
+            until: utc("2024-05-05T11:00:00Z"),
+        };
+        split_work(&mut sessions, 0, away, SessionType::Break, now);
+        assert_eq!(sessions[1].end_time, Some(now));
+        assert_eq!(sessions[2].start_time, now);
+
+        let mut sessions = vec![running_work("2024-05-05T09:00:00Z")];
+        let backwards = Away {
+            since: utc("2024-05-05T09:30:00Z"),
+            until: utc("2024-05-05T09:10:00Z"),
+        };
+        split_work(&mut sessions, 0, backwards, SessionType::Break, now);
+        assert_eq!(sessions[1].end_time, Some(backwards.since));
+        assert_eq!(sessions[2].start_time, backwards.since);
+    }
+}
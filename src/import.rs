@@ -0,0 +1,443 @@
+use crate::calendar::calendar;
+use crate::data::{LogEvent, Session, SessionType, Store, Timeline};
+use crate::ui::format_duration_str;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::File,
+    io::Read,
+    path::PathBuf,
+};
+
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%m/%d/%Y", "%d.%m.%Y", "%d-%m-%Y"];
+const TIME_FORMATS: &[&str] = &["%H:%M:%S", "%I:%M:%S %p", "%H:%M", "%I:%M %p"];
+
+/// What to do with rows not marked as billable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NonBillable {
+    Work,
+    Break,
+    Skip,
+}
+
+#[derive(Debug)]
+pub struct ImportOptions {
+    pub path: PathBuf,
+    pub utc: bool,
+    pub non_billable: NonBillable,
+    /// Overrides the guessed date format, e.g. `%d/%m/%Y`.
+    pub date_format: Option<String>,
+    /// Without it only the summary is printed.
+    pub apply: bool,
+}
+
+/// Column positions of a detailed report. Toggl Track and Clockify use the
+/// same names, only capitalised differently.
+struct Columns {
+    description: usize,
+    project: Option<usize>,
+    tags: Option<usize>,
+    billable: Option<usize>,
+    start_date: usize,
+    start_time: usize,
+    end_date: usize,
+    end_time: usize,
+}
+
+impl Columns {
+    fn find(headers: &csv::StringRecord) -> Result<Self> {
+        let names: Vec<String> = headers
+            .iter()
+            .map(|h| h.trim_start_matches('\u{feff}').trim().to_lowercase())
+            .collect();
+        let column = |name: &str| names.iter().position(|n| n == name);
+        let required = |name: &str| {
+            column(name).with_context(|| {
+                format!(
+                    "missing column \"{name}\", expected a Toggl Track or Clockify detailed report"
+                )
+            })
+        };
+        Ok(Columns {
+            description: required("description")?,
+            project: column("project"),
+            tags: column("tags"),
+            billable: column("billable"),
+            start_date: required("start date")?,
+            start_time: required("start time")?,
+            end_date: required("end date")?,
+            end_time: required("end time")?,
+        })
+    }
+}
+
+/// Clockify adds decimal durations; Toggl only has `Duration`.
+fn source_name(headers: &csv::StringRecord) -> &'static str {
+    if headers
+        .iter()
+        .any(|h| h.eq_ignore_ascii_case("duration (decimal)"))
+    {
+        "Clockify"
+    } else {
+        "Toggl Track"
+    }
+}
+
+impl ImportOptions {
+    fn parse_date(&self, value: &str) -> Option<NaiveDate> {
+        match &self.date_format {
+            Some(format) => NaiveDate::parse_from_str(value, format).ok(),
+            None => DATE_FORMATS
+                .iter()
+                .find_map(|f| NaiveDate::parse_from_str(value, f).ok()),
+        }
+    }
+
+    fn parse_time(&self, date: &str, time: &str) -> Result<DateTime<Utc>> {
+        let date = self
+            .parse_date(date.trim())
+            .with_context(|| format!("invalid date {date:?}, try --date-format"))?;
+        let time = TIME_FORMATS
+            .iter()
+            .find_map(|f| NaiveTime::parse_from_str(time.trim(), f).ok())
+            .with_context(|| format!("invalid time {time:?}"))?;
+        let naive = NaiveDateTime::new(date, time);
+        if self.utc {
+            return Ok(naive.and_utc());
+        }
+        // Ambiguous local times (DST fall-back) take the earlier instant
+        calendar()
+            .zone
+            .instant_at(&naive)
+            .with_context(|| format!("{naive} does not exist in the reporting timezone"))
+    }
+
+    fn session(&self, columns: &Columns, row: &csv::StringRecord) -> Result<Option<Session>> {
+        let field = |i: usize| row.get(i).unwrap_or_default();
+        let billable = columns
+            .billable
+            .is_none_or(|i| field(i).trim().eq_ignore_ascii_case("yes"));
+        let session_type = match (billable, self.non_billable) {
+            (true, _) | (false, NonBillable::Work) => SessionType::Work,
+            (false, NonBillable::Break) => SessionType::Break,
+            (false, NonBillable::Skip) => return Ok(None),
+        };
+        let start = self.parse_time(field(columns.start_date), field(columns.start_time))?;
+        let end = self.parse_time(field(columns.end_date), field(columns.end_time))?;
+        if end <= start {
+            bail!("ends before it starts");
+        }
+        Ok(Some(Session {
+            start_time: start,
+            end_time: Some(end),
+            session_type,
+            note: field(columns.description).trim().to_string(),
+            project: columns
+                .project
+                .map(|i| field(i).trim().to_string())
+                .filter(|p| !p.is_empty()),
+            tags: columns.tags.map_or_else(Vec::new, |i| {
+                field(i)
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }),
+            zone: Some(if self.utc {
+                "UTC".to_string()
+            } else {
+                calendar().zone.name()
+            }),
+            uid: None,
+        }))
+    }
+}
+
+/// What an import would do, before anything is saved.
+#[derive(Default)]
+struct Plan {
+    source: &'static str,
+    rows: usize,
+    new: Vec<Session>,
+    duplicates: usize,
+    overlapping: usize,
+    skipped: usize,
+}
+
+/// Sorts the rows of the report into new sessions and ones the log already
+/// has. A row is a duplicate when a session with the same start and end
+/// (to the second) exists, so importing the same report twice is harmless.
+/// Rows overlapping tracked work, a break or the running session are left
+/// out rather than double-counting that time; idle time may be filled in.
+fn plan(options: &ImportOptions, existing: &[Session]) -> Result<Plan> {
+    let file =
+        File::open(&options.path).with_context(|| format!("reading {}", options.path.display()))?;
+    plan_from(options, file, existing)
+}
+
+/// Like `plan`, for a report read from `input`. `existing` is sorted by start.
+fn plan_from(options: &ImportOptions, input: impl Read, existing: &[Session]) -> Result<Plan> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(input);
+    let headers = reader.headers()?.clone();
+    let columns = Columns::find(&headers)?;
+
+    let key = |s: &Session| (s.start_time.timestamp(), s.end_time.map(|t| t.timestamp()));
+    let mut seen: HashSet<_> = existing.iter().map(key).collect();
+    let timeline = Timeline::new(existing);
+    // Rows taken so far by start, which never overlap each other
+    let mut taken = BTreeMap::new();
+    let mut plan = Plan {
+        source: source_name(&headers),
+        ..Plan::default()
+    };
+    for (i, row) in reader.records().enumerate() {
+        // Row 1 is the header
+        let line = i + 2;
+        let row = row.with_context(|| format!("row {line}"))?;
+        plan.rows += 1;
+        let Some(session) = options
+            .session(&columns, &row)
+            .with_context(|| format!("row {line}"))?
+        else {
+            plan.skipped += 1;
+            continue;
+        };
+        let (start, end) = (
+            session.start_time,
+            session.end_time.unwrap_or(session.start_time),
+        );
+        let tracked = timeline
+            .overlapping(existing, start, end)
+            .into_iter()
+            .map(|i| &existing[i])
+            .any(|s| s.session_type != SessionType::Idle || s.end_time.is_none());
+        let clashes = taken
+            .range(..end)
+            .next_back()
+            .is_some_and(|(_, taken_end)| *taken_end > start);
+        if !seen.insert(key(&session)) {
+            plan.duplicates += 1;
+        } else if tracked || clashes {
+            plan.overlapping += 1;
+        } else {
+            taken.insert(start, end);
+            plan.new.push(session);
+        }
+    }
+    plan.new.sort_by_key(|s| s.start_time);
+    Ok(plan)
+}
+
+fn print_summary(options: &ImportOptions, plan: &Plan) {
+    println!(
+        "{} report {}: {} rows",
+        plan.source,
+        options.path.display(),
+        plan.rows
+    );
+    if let (Some(first), Some(last)) = (plan.new.first(), plan.new.last()) {
+        let total = |kind: SessionType| {
+            plan.new
+                .iter()
+                .filter(|s| s.session_type == kind)
+                .fold(Duration::zero(), |sum, s| sum + s.duration())
+        };
+        println!(
+            "  {} new sessions from {} to {} (work {}, break {})",
+            plan.new.len(),
+            calendar().date_of(first.start_time),
+            calendar().date_of(last.start_time),
+            format_duration_str(total(SessionType::Work)),
+            format_duration_str(total(SessionType::Break)),
+        );
+    } else {
+        println!("  no new sessions");
+    }
+    println!("  {} already in the log", plan.duplicates);
+    println!("  {} overlap tracked sessions", plan.overlapping);
+    if options.non_billable == NonBillable::Skip {
+        println!("  {} non-billable skipped", plan.skipped);
+    }
+}
+
+/// `import` command: prints what the report adds and, with `--apply`, saves it.
+pub fn run(mut store: Box<dyn Store>, options: &ImportOptions) -> Result<()> {
+    if !options.apply {
+        // `load` may compact or close open sessions; a dry run only reads
+        let sessions = store.sessions_between(DateTime::UNIX_EPOCH, Utc::now())?;
+        let plan = plan(options, &sessions)?;
+        print_summary(options, &plan);
+        if !plan.new.is_empty() {
+            println!("Dry run, nothing was saved. Run again with --apply to import.");
+        }
+        return Ok(());
+    }
+
+    let mut sessions = store.load()?.sessions;
+    let plan = plan(options, &sessions)?;
+    print_summary(options, &plan);
+    let mut events = Vec::with_capacity(plan.new.len());
+    for session in plan.new {
+        let index = sessions.partition_point(|s| s.start_time <= session.start_time);
+        sessions.insert(index, session.clone());
+        events.push(LogEvent::Inserted { index, session });
+    }
+    if !events.is_empty() {
+        store.record(&sessions, &events)?;
+        println!("Imported {} sessions.", events.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{session, utc};
+
+    const TOGGL: &str = "\u{feff}User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags\n";
+    const CLOCKIFY: &str = "Project,Client,Description,Task,User,Group,Email,Tags,Billable,Start Date,Start Time,End Date,End Time,Duration (h),Duration (decimal)\n";
+
+    fn options() -> ImportOptions {
+        ImportOptions {
+            path: PathBuf::new(),
+            utc: true,
+            non_billable: NonBillable::Work,
+            date_format: None,
+            apply: false,
+        }
+    }
+
+    /// A Toggl row for the `acme` project, from `start` to `end` on `date`.
+    fn toggl_row(date: &str, start: &str, end: &str, billable: &str) -> String {
+        format!("me,me@x,,acme,,api,{billable},{date},{start},{date},{end},,\"a, b\"\n")
+    }
+
+    fn plan_csv(options: &ImportOptions, csv: &str, existing: &[Session]) -> Plan {
+        plan_from(options, csv.as_bytes(), existing).unwrap()
+    }
+
+    #[test]
+    fn reads_toggl_and_clockify_headers() {
+        let toggl = plan_csv(
+            &options(),
+            &(TOGGL.to_string() + &toggl_row("2024-05-05", "09:00:00", "10:00:00", "Yes")),
+            &[],
+        );
+        assert_eq!(toggl.source, "Toggl Track");
+        let [work] = toggl.new.as_slice() else {
+            panic!("expected one session");
+        };
+        assert_eq!(work.start_time, utc("2024-05-05T09:00:00Z"));
+        assert_eq!(work.note, "api");
+        assert_eq!(work.project.as_deref(), Some("acme"));
+        assert_eq!(work.tags, ["a", "b"]);
+
+        let clockify = plan_csv(
+            &options(),
+            &(CLOCKIFY.to_string()
+                + "acme,,api,,me,,me@x,,Yes,05/05/2024,09:00:00 AM,05/05/2024,10:30:00 AM,1:30,1.5\n"),
+            &[],
+        );
+        assert_eq!(clockify.source, "Clockify");
+        assert_eq!(clockify.new[0].end_time, Some(utc("2024-05-05T10:30:00Z")));
+
+        let missing = plan_from(&options(), "Description,Start date\n".as_bytes(), &[]);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn dates_fall_back_through_the_known_formats() {
+        let guessed = options();
+        for date in ["2024-05-06", "05/06/2024", "06.05.2024", "06-05-2024"] {
+            assert_eq!(
+                guessed.parse_time(date, "13:05").unwrap(),
+                utc("2024-05-06T13:05:00Z"),
+                "{date}"
+            );
+        }
+        assert_eq!(
+            guessed.parse_time("2024-05-06", "1:05:30 PM").unwrap(),
+            utc("2024-05-06T13:05:30Z")
+        );
+        let day_first = ImportOptions {
+            date_format: Some("%d/%m/%Y".to_string()),
+            ..options()
+        };
+        assert_eq!(
+            day_first.parse_time("05/06/2024", "13:05").unwrap(),
+            utc("2024-06-05T13:05:00Z")
+        );
+        assert!(day_first.parse_time("2024-06-05", "13:05").is_err());
+    }
+
+    #[test]
+    fn duplicates_match_to_the_second() {
+        let existing = [session(
+            SessionType::Work,
+            "2024-05-05T09:00:00.400Z",
+            Some("2024-05-05T10:00:00.900Z"),
+        )];
+        let csv = TOGGL.to_string()
+            + &toggl_row("2024-05-05", "09:00:00", "10:00:00", "Yes")
+            + &toggl_row("2024-05-05", "11:00:00", "12:00:00", "Yes")
+            + &toggl_row("2024-05-05", "11:00:00", "12:00:00", "Yes");
+        let plan = plan_csv(&options(), &csv, &existing);
+        assert_eq!((plan.new.len(), plan.duplicates), (1, 2));
+    }
+
+    #[test]
+    fn non_billable_rows_follow_the_option() {
+        let csv = TOGGL.to_string()
+            + &toggl_row("2024-05-05", "09:00:00", "10:00:00", "Yes")
+            + &toggl_row("2024-05-05", "11:00:00", "12:00:00", "No");
+        let kinds = |non_billable| {
+            let plan = plan_csv(
+                &ImportOptions {
+                    non_billable,
+                    ..options()
+                },
+                &csv,
+                &[],
+            );
+            let kinds: Vec<_> = plan.new.iter().map(|s| s.session_type).collect();
+            (kinds, plan.skipped)
+        };
+        use SessionType::{Break, Work};
+        assert_eq!(kinds(NonBillable::Work), (vec![Work, Work], 0));
+        assert_eq!(kinds(NonBillable::Break), (vec![Work, Break], 0));
+        assert_eq!(kinds(NonBillable::Skip), (vec![Work], 1));
+    }
+
+    #[test]
+    fn idle_time_may_be_filled_but_tracked_time_not() {
+        let existing = [
+            session(
+                SessionType::Idle,
+                "2024-05-05T08:00:00Z",
+                Some("2024-05-05T12:00:00Z"),
+            ),
+            session(
+                SessionType::Work,
+                "2024-05-05T12:00:00Z",
+                Some("2024-05-05T13:00:00Z"),
+            ),
+            session(SessionType::Idle, "2024-05-05T17:00:00Z", None),
+        ];
+        let csv = TOGGL.to_string()
+            + &toggl_row("2024-05-05", "09:00:00", "10:00:00", "Yes")
+            + &toggl_row("2024-05-05", "11:30:00", "12:30:00", "Yes")
+            + &toggl_row("2024-05-05", "09:30:00", "10:30:00", "Yes")
+            + &toggl_row("2024-05-05", "14:00:00", "15:00:00", "Yes")
+            + &toggl_row("2024-05-05", "16:00:00", "18:00:00", "Yes");
+        let plan = plan_csv(&options(), &csv, &existing);
+        let starts: Vec<_> = plan.new.iter().map(|s| s.start_time).collect();
+        assert_eq!(
+            starts,
+            [utc("2024-05-05T09:00:00Z"), utc("2024-05-05T14:00:00Z")]
+        );
+        // Into tracked work, into an earlier row, and into the running session
+        assert_eq!(plan.overlapping, 3);
+    }
+}
@@ -0,0 +1,374 @@
+use crate::data::{
+    Incoming, LoadedLog, LogEvent, Session, Store, close_open_sessions, load_with_recovery,
+    lock_log, merge_sessions, read_sessions, sibling, write_atomic,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Compacted state. `seq` is the last journal entry folded into `sessions`.
+#[derive(Default, Serialize, Deserialize)]
+struct Snapshot {
+    seq: u64,
+    sessions: Vec<Session>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    seq: u64,
+    #[serde(flatten)]
+    event: LogEvent,
+}
+
+/// Append-only backend: every change is one JSON line in `<log>.journal`,
+/// folded into `<log>.snapshot` every `compact_every` entries and on load.
+///
+/// Journal entries carry a sequence number, so a crash between writing the
+/// snapshot and truncating the journal never replays an event twice.
+///
+/// Like the JSON store, it reads and writes under the log lock, and when
+/// another instance wrote to the files in between, merges its sessions in
+/// and writes a fresh snapshot instead of appending events that assume our
+/// list.
+pub struct JournalStore {
+    legacy_path: PathBuf,
+    snapshot_path: PathBuf,
+    journal_path: PathBuf,
+    keep_backups: usize,
+    compact_every: usize,
+    seq: u64,
+    pending: usize,
+    /// The list as the app last saw it, the base of the three-way merge.
+    base: Vec<Session>,
+    /// The files as we last left them.
+    written: Option<FileState>,
+    merged: bool,
+    reset: Option<Vec<Session>>,
+}
+
+/// Size and age of the journal and snapshot, which any write changes.
+#[derive(PartialEq)]
+struct FileState {
+    journal: Option<u64>,
+    snapshot: Option<(u64, SystemTime)>,
+}
+
+impl JournalStore {
+    pub fn new(path: PathBuf, keep_backups: usize, compact_every: usize) -> Self {
+        JournalStore {
+            snapshot_path: sibling(&path, ".snapshot"),
+            journal_path: sibling(&path, ".journal"),
+            legacy_path: path,
+            keep_backups,
+            compact_every: compact_every.max(1),
+            seq: 0,
+            pending: 0,
+            base: Vec::new(),
+            written: None,
+            merged: false,
+            reset: None,
+        }
+    }
+
+    fn file_state(&self) -> Result<FileState> {
+        let metadata = |path: &Path| match fs::metadata(path) {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        };
+        Ok(FileState {
+            journal: metadata(&self.journal_path)?.map(|m| m.len()),
+            snapshot: match metadata(&self.snapshot_path)? {
+                Some(m) => Some((m.len(), m.modified()?)),
+                None => None,
+            },
+        })
+    }
+
+    /// The snapshot with the journal replayed on top, as stored.
+    fn read(&self) -> Result<(Vec<Session>, u64, Option<PathBuf>)> {
+        let (snapshot, recovered_from) = load_with_recovery::<Snapshot>(&self.snapshot_path)?;
+        let (sessions, seq) = self.replay(snapshot)?;
+        Ok((sessions, seq, recovered_from))
+    }
+
+    /// Applies the journal entries newer than the snapshot on top of it.
+    fn replay(&self, snapshot: Snapshot) -> Result<(Vec<Session>, u64)> {
+        let mut sessions = snapshot.sessions;
+        let mut seq = snapshot.seq;
+        for entry in read_journal(&self.journal_path)? {
+            if entry.seq > seq {
+                entry.event.apply(&mut sessions)?;
+                seq = entry.seq;
+            }
+        }
+        Ok((sessions, seq))
+    }
+
+    fn compact(&mut self, sessions: &[Session]) -> Result<()> {
+        let snapshot = Snapshot {
+            seq: self.seq,
+            sessions: sessions.to_vec(),
+        };
+        let data = serde_json::to_string(&snapshot)?;
+        write_atomic(&self.snapshot_path, data.as_bytes(), self.keep_backups)?;
+        File::create(&self.journal_path)?.sync_all()?;
+        self.pending = 0;
+        self.written = Some(self.file_state()?);
+        Ok(())
+    }
+}
+
+impl Store for JournalStore {
+    fn load(&mut self) -> Result<LoadedLog> {
+        let _lock = lock_log(&self.legacy_path)?;
+        let (mut sessions, recovered_from) =
+            if !self.snapshot_path.exists() && !self.journal_path.exists() {
+                // First run on this backend: import the pretty-JSON log if there is one
+                read_sessions(&self.legacy_path)?
+            } else {
+                let (sessions, seq, recovered_from) = self.read()?;
+                self.seq = seq;
+                (sessions, recovered_from)
+            };
+        close_open_sessions(&mut sessions);
+        self.compact(&sessions)?;
+        self.base = sessions.clone();
+        Ok(LoadedLog {
+            sessions,
+            recovered_from,
+        })
+    }
+
+    fn record(&mut self, sessions: &[Session], events: &[LogEvent]) -> Result<()> {
+        let _lock = lock_log(&self.legacy_path)?;
+        if self.reset.is_some() || Some(self.file_state()?) != self.written {
+            // Our events are relative to our list, which is no longer the stored one
+            let (theirs, seq, _) = self.read()?;
+            self.seq = seq;
+            let merged = merge_sessions(&self.base, sessions, &theirs);
+            if merged != sessions {
+                self.merged = true;
+                self.reset = Some(merged.clone());
+            }
+            return self.compact(&merged);
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.journal_path)?;
+        for event in events {
+            self.seq += 1;
+            let entry = Entry {
+                seq: self.seq,
+                event: event.clone(),
+            };
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        }
+        file.sync_data()?;
+        self.written = Some(self.file_state()?);
+        self.base = sessions.to_vec();
+
+        self.pending += events.len();
+        if self.pending >= self.compact_every {
+            self.compact(sessions)?;
+        }
+        Ok(())
+    }
+
+    fn incoming(&mut self) -> Vec<Incoming> {
+        match self.reset.take() {
+            Some(sessions) => {
+                self.base = sessions.clone();
+                vec![Incoming::Reset(sessions)]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn warning(&self) -> Option<String> {
+        self.merged
+            .then(|| "Another instance changed the log, its sessions were merged".to_string())
+    }
+
+    fn sessions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Session>> {
+        // Read like `load`, without compacting or writing anything back
+        let (mut sessions, _, _) = {
+            let _lock = lock_log(&self.legacy_path)?;
+            self.read()?
+        };
+        close_open_sessions(&mut sessions);
+        Ok(sessions
+            .into_iter()
+            .filter(|s| s.overlaps(from, to))
+            .collect())
+    }
+}
+
+/// Reads all journal entries. A torn final line from a crash mid-append is
+/// dropped; a bad line anywhere else is an error.
+fn read_journal(path: &Path) -> Result<Vec<Entry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let lines: Vec<String> = BufReader::new(File::open(path)?)
+        .lines()
+        .collect::<std::io::Result<_>>()?;
+    let mut entries = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(_) if i + 1 == lines.len() => break,
+            Err(err) => {
+                return Err(err).with_context(|| format!("{} line {}", path.display(), i + 1));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SessionType;
+    use crate::testing::{TempDir, session};
+    use chrono::Duration;
+
+    fn store(dir: &TempDir) -> JournalStore {
+        JournalStore::new(dir.join("work_log.json"), 0, 100)
+    }
+
+    fn write_lines(path: &Path, lines: &[String]) {
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    fn entry(seq: u64, session: &Session) -> String {
+        serde_json::to_string(&Entry {
+            seq,
+            event: LogEvent::Started {
+                session: session.clone(),
+            },
+        })
+        .unwrap()
+    }
+
+    fn work(start: &str) -> Session {
+        session(SessionType::Work, start, Some("2024-05-05T23:00:00Z"))
+    }
+
+    #[test]
+    fn replays_the_journal_on_load() {
+        let dir = TempDir::new();
+        let mut first = store(&dir);
+        let mut sessions = first.load().unwrap().sessions;
+        for session in [work("2024-05-05T09:00:00Z"), work("2024-05-05T11:00:00Z")] {
+            let event = LogEvent::Started { session };
+            event.apply(&mut sessions).unwrap();
+            first.record(&sessions, &[event]).unwrap();
+        }
+        let journal = fs::read_to_string(dir.join("work_log.json.journal")).unwrap();
+        assert_eq!(journal.lines().count(), 2);
+        assert_eq!(store(&dir).load().unwrap().sessions, sessions);
+    }
+
+    #[test]
+    fn skips_entries_already_in_the_snapshot() {
+        // A crash after writing the snapshot but before truncating the journal
+        let dir = TempDir::new();
+        let (a, b, c) = (
+            work("2024-05-05T09:00:00Z"),
+            work("2024-05-05T10:00:00Z"),
+            work("2024-05-05T11:00:00Z"),
+        );
+        let snapshot = Snapshot {
+            seq: 2,
+            sessions: vec![a.clone(), b.clone()],
+        };
+        fs::write(
+            dir.join("work_log.json.snapshot"),
+            serde_json::to_string(&snapshot).unwrap(),
+        )
+        .unwrap();
+        write_lines(
+            &dir.join("work_log.json.journal"),
+            &[entry(1, &a), entry(2, &b), entry(3, &c)],
+        );
+        assert_eq!(store(&dir).load().unwrap().sessions, [a, b, c]);
+    }
+
+    #[test]
+    fn drops_a_torn_last_line() {
+        let dir = TempDir::new();
+        let a = work("2024-05-05T09:00:00Z");
+        let journal = dir.join("work_log.json.journal");
+        let torn = entry(2, &work("2024-05-05T10:00:00Z"))[..20].to_string();
+        write_lines(&journal, &[entry(1, &a), torn.clone()]);
+        assert_eq!(store(&dir).load().unwrap().sessions, vec![a.clone()]);
+
+        // Anywhere but at the end it is corruption
+        write_lines(&journal, &[torn, entry(1, &a)]);
+        assert!(
+            store(&dir)
+                .sessions_between(DateTime::UNIX_EPOCH, Utc::now())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn ranges_close_sessions_left_open() {
+        let dir = TempDir::new();
+        let now = Utc::now();
+        let earlier = Session {
+            start_time: now - Duration::hours(2),
+            ..session(SessionType::Work, "2024-05-05T09:00:00Z", None)
+        };
+        let later = Session {
+            start_time: now - Duration::hours(1),
+            ..session(SessionType::Break, "2024-05-05T09:00:00Z", None)
+        };
+        write_lines(
+            &dir.join("work_log.json.journal"),
+            &[entry(1, &earlier), entry(2, &later)],
+        );
+        let sessions = store(&dir)
+            .sessions_between(now - Duration::days(1), now)
+            .unwrap();
+        assert_eq!(sessions[0].end_time, Some(later.start_time));
+        assert_eq!(sessions[1].end_time, None);
+    }
+
+    #[test]
+    fn merges_what_another_instance_wrote() {
+        let dir = TempDir::new();
+        let (mut ours, mut theirs) = (store(&dir), store(&dir));
+        let mut our_sessions = ours.load().unwrap().sessions;
+        let mut their_sessions = theirs.load().unwrap().sessions;
+
+        let event = LogEvent::Started {
+            session: work("2024-05-05T09:00:00Z"),
+        };
+        event.apply(&mut their_sessions).unwrap();
+        theirs.record(&their_sessions, &[event]).unwrap();
+
+        let event = LogEvent::Started {
+            session: work("2024-05-05T11:00:00Z"),
+        };
+        event.apply(&mut our_sessions).unwrap();
+        ours.record(&our_sessions, &[event]).unwrap();
+
+        let both = vec![work("2024-05-05T09:00:00Z"), work("2024-05-05T11:00:00Z")];
+        assert!(ours.warning().is_some());
+        assert!(matches!(ours.incoming().as_slice(), [Incoming::Reset(s)] if *s == both));
+        assert_eq!(store(&dir).load().unwrap().sessions, both);
+    }
+}
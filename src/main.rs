@@ -1,187 +1,892 @@
+mod animation;
 mod assets;
+mod calendar;
+mod cli;
+mod config;
+#[cfg(unix)]
+mod daemon;
 mod data;
+mod editor;
+mod export;
+mod goals;
+mod idle;
+mod import;
+mod journal;
+mod pet;
+mod pomodoro;
+mod reminders;
+mod report;
+mod skin;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(test)]
+mod testing;
 mod ui;
 
+use crate::animation::Animation;
+use crate::calendar::{calendar, set_calendar};
+use crate::cli::{CliArgs, Command};
+use crate::config::Config;
 use crate::data::*;
+use crate::editor::{Field, SessionForm};
+use crate::export::{ExportFormat, ExportOptions};
+use crate::goals::GoalConfig;
+use crate::idle::{Away, IdleWatch};
+use crate::pet::PetState;
+use crate::pomodoro::{Pomodoro, PomodoroConfig};
+use crate::reminders::{Popup, Reminders};
+use crate::report::{DayTotal, HeatmapView, ReportView};
+use crate::skin::Skin;
 use anyhow::Result;
-use chrono::{Duration, Local, NaiveDate, Utc};
+use chrono::{Duration, NaiveDate, Utc};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEventKind,
+    },
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    style::Print,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{prelude::*, widgets::TableState};
-use std::{io, time::Instant};
+use std::{collections::HashSet, io, path::PathBuf, time::Instant};
+
+/// How long the pet celebrates a reached goal.
+const CELEBRATION: std::time::Duration = std::time::Duration::from_secs(60);
 
 struct App {
+    store: Box<dyn Store>,
     sessions: Vec<Session>,
+    /// Day lookups into `sessions`, rebuilt whenever they change.
+    timeline: Timeline,
     current_session_index: Option<usize>,
     input_mode: InputMode,
     input_buffer: String,
+    skin: Skin,
+    animation: Animation,
     animation_index: usize,
+    /// When the current animation frame went up.
+    frame_shown: Instant,
     selected_date: NaiveDate,
     table_state: TableState,
     editing_history_index: Option<usize>,
+    /// Start/end/type form of the history editor.
+    session_form: Option<SessionForm>,
     cached_today_stats: (Duration, Duration),
+    pomodoro_config: PomodoroConfig,
+    pomodoro: Option<Pomodoro>,
+    goals: GoalConfig,
+    /// Consecutive workdays the goal was met, up to today.
+    goal_streak: u32,
+    /// The `?` key help is open.
+    show_keys: bool,
+    /// Streak of the days before the given day, see `update_goal_streak`.
+    streak_before: Option<(NaiveDate, u32)>,
+    /// First day and today of the heatmap with its day totals, see `update_heatmap`.
+    heatmap_days: Option<(NaiveDate, NaiveDate, Vec<DayTotal>)>,
+    goal_met_today: bool,
+    /// The pet celebrates a goal reached until then.
+    celebrate_until: Option<Instant>,
+    pet: PetState,
+    pet_path: PathBuf,
+    reminders: Reminders,
+    /// Reminder shown on top of everything until dismissed or snoozed.
+    reminder: Option<Popup>,
+    idle: IdleWatch,
+    /// Time away from a work session, waiting for what to do with it.
+    away: Option<Away>,
+    view: View,
+    notice: Option<String>,
+    export_dir: PathBuf,
+    save_error: Option<anyhow::Error>,
+}
+
+/// What the lower half of the screen shows.
+#[derive(Clone, Copy)]
+enum View {
+    History,
+    Report(ReportView),
+    Heatmap(HeatmapView),
 }
 
 #[derive(PartialEq)]
 enum InputMode {
     Normal,
     EditingNote,
+    PickingProject,
+    EditingSession,
+    SplittingSession,
 }
 
 impl App {
-    fn new() -> Self {
-        let mut sessions = load_sessions().unwrap_or_default();
-
-        // Create new idle session
-        let idle_session = Session {
-            start_time: Utc::now(),
-            end_time: None,
-            session_type: SessionType::Idle,
-            note: String::new(),
+    fn new(config: &Config, store: Box<dyn Store>) -> Result<Self> {
+        Ok(App::open(config, store, SessionType::Idle)?.0)
+    }
+
+    /// Like `new`, but a log with no running session gets a `first` session
+    /// instead of an idle one. Also returns whether it was opened.
+    fn open(
+        config: &Config,
+        mut store: Box<dyn Store>,
+        first: SessionType,
+    ) -> Result<(Self, bool)> {
+        let loaded = store.load()?;
+        let notice = loaded.recovered_from.map(|backup| {
+            format!(
+                "Log was unreadable, restored from backup {}",
+                backup.display()
+            )
+        });
+        let mut sessions = loaded.sessions;
+        let (skin, skin_error) = Skin::from_config(config);
+        let notice = match skin_error {
+            Some(err) => Some(format!("Using the built-in pet, {err:#}")),
+            None => notice,
         };
 
-        sessions.push(idle_session);
+        // Resume a session still running from an earlier run or a CLI
+        // command, otherwise open a new one
+        let mut started = None;
+        if sessions.last().is_none_or(|s| s.end_time.is_some()) {
+            let session = Session::new(first, Utc::now());
+            started = Some(LogEvent::Started {
+                session: session.clone(),
+            });
+            sessions.push(session);
+        }
         let idx = sessions.len() - 1;
 
         let mut app = App {
+            store,
+            timeline: Timeline::new(&sessions),
             sessions,
             current_session_index: Some(idx),
             input_mode: InputMode::Normal,
             input_buffer: String::new(),
+            skin,
+            animation: Animation::Idle,
             animation_index: 0,
-            selected_date: Local::now().date_naive(),
+            frame_shown: Instant::now(),
+            selected_date: calendar().today(),
             table_state: TableState::default(),
             editing_history_index: None,
+            session_form: None,
             cached_today_stats: (Duration::zero(), Duration::zero()),
+            pomodoro_config: config.pomodoro,
+            pomodoro: None,
+            goals: config.goals,
+            goal_streak: 0,
+            show_keys: false,
+            streak_before: None,
+            heatmap_days: None,
+            goal_met_today: false,
+            celebrate_until: None,
+            pet: PetState::default(),
+            pet_path: config.pet_file.clone().unwrap_or_default(),
+            reminders: Reminders::new(config.reminders.clone()),
+            reminder: None,
+            idle: IdleWatch::new(config.idle.clone()),
+            away: None,
+            view: View::History,
+            notice,
+            export_dir: config.export_dir.clone().unwrap_or_default(),
+            save_error: None,
         };
 
+        let opened = started.is_some();
+        if let Some(started) = started {
+            app.record(&[started]);
+        }
         app.update_stats_cache();
-        app
+        app.goal_met_today = app.goal_met(calendar().today()) == Some(true);
+        match PetState::load(&app.pet_path) {
+            Ok(pet) => app.pet = pet,
+            Err(err) => app.notice = Some(format!("Could not load pet: {err:#}")),
+        }
+        app.update_pet();
+        Ok((app, opened))
+    }
+
+    fn record(&mut self, events: &[LogEvent]) {
+        self.timeline = Timeline::new(&self.sessions);
+        self.streak_before = None;
+        self.heatmap_days = None;
+        if let Err(err) = self.store.record(&self.sessions, events) {
+            self.notice = Some(format!("Could not save log: {err:#}"));
+            self.save_error = Some(err);
+        }
+    }
+
+    /// Returns the first save error since the last call, if any.
+    fn flush(&mut self) -> Result<()> {
+        match self.save_error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Replaces the session list with `events` applied to it, leaving it
+    /// untouched if any of them does not fit.
+    fn apply_events(&mut self, events: &[LogEvent]) -> Result<()> {
+        let mut sessions = self.sessions.clone();
+        for event in events {
+            event.apply(&mut sessions)?;
+        }
+        self.sessions = sessions;
+        self.timeline = Timeline::new(&self.sessions);
+        self.streak_before = None;
+        self.heatmap_days = None;
+        self.current_session_index = self.sessions.len().checked_sub(1);
+        self.update_stats_cache();
+        Ok(())
+    }
+
+    /// Picks up changes other daemon clients made since the last call.
+    fn sync_remote(&mut self) {
+        for update in self.store.incoming() {
+            match update {
+                Incoming::Events(events) => {
+                    self.apply_events(&events).ok();
+                }
+                Incoming::Reset(sessions) => {
+                    let current = self.get_active_session().start_time;
+                    self.sessions = sessions;
+                    self.apply_events(&[]).ok();
+                    // Stay on our own session if it is still running
+                    if let Some(idx) = self
+                        .sessions
+                        .iter()
+                        .position(|s| s.start_time == current && s.end_time.is_none())
+                    {
+                        self.current_session_index = Some(idx);
+                    }
+                }
+            }
+        }
     }
 
     fn update_stats_cache(&mut self) {
-        let mut total_work = Duration::zero();
-        let mut total_break = Duration::zero();
+        let day = &report::day_totals(
+            &self.sessions,
+            &self.timeline,
+            self.selected_date,
+            self.selected_date,
+        )[0];
+        self.cached_today_stats = (day.work, day.rest);
+        self.update_goal_streak();
+    }
 
-        for s in self
-            .sessions
-            .iter()
-            .filter(|s| s.start_time_local().date_naive() == self.selected_date)
-        {
-            let dur = s.duration();
-            match s.session_type {
-                SessionType::Work => total_work = total_work + dur,
-                SessionType::Break => total_break = total_break + dur,
-                _ => {}
+    /// Today's streak: the days before it are only counted again on a new
+    /// day or after the history changed, today is added once its goal is met.
+    fn update_goal_streak(&mut self) {
+        let today = calendar().today();
+        let before = match self.streak_before {
+            Some((date, streak)) if date == today => streak,
+            _ => {
+                let streak = self
+                    .goals
+                    .streak_before(&self.sessions, &self.timeline, today);
+                self.streak_before = Some((today, streak));
+                streak
             }
+        };
+        let work = report::day_totals(&self.sessions, &self.timeline, today, today)[0].work;
+        self.goal_streak = self.goals.streak_with_today(before, today, work);
+    }
+
+    /// Totals the days of the heatmap grid. Past days are only counted again
+    /// when the grid scrolls, on a new day or after the history changed;
+    /// today's total grows with the running session and is redone every time.
+    fn update_heatmap(&mut self, view: &HeatmapView) {
+        let first = view.first_week();
+        let today = calendar().today();
+        if !matches!(&self.heatmap_days, Some((f, t, _)) if *f == first && *t == today) {
+            let last = view.last_week + Duration::days(6);
+            let days = report::day_totals(&self.sessions, &self.timeline, first, last);
+            self.heatmap_days = Some((first, today, days));
+        } else if let Some((_, _, days)) = &mut self.heatmap_days
+            && let Ok(i) = usize::try_from((today - first).num_days())
+            && let Some(day) = days.get_mut(i)
+        {
+            *day = report::day_totals(&self.sessions, &self.timeline, today, today).remove(0);
+        }
+    }
+
+    /// Whether the work goal of `date` was met; `None` on days without one.
+    fn goal_met(&self, date: NaiveDate) -> Option<bool> {
+        let work = report::day_totals(&self.sessions, &self.timeline, date, date)[0].work;
+        self.goals.met(date, work)
+    }
+
+    /// Counts the days finished since the pet was last saved.
+    fn update_pet(&mut self) {
+        let stage = self.pet.stage();
+        let today = calendar().today();
+        if !self
+            .pet
+            .catch_up(&self.sessions, &self.timeline, &self.goals, today)
+        {
+            return;
+        }
+        if let Err(err) = self.pet.save(&self.pet_path) {
+            self.notice = Some(format!("Could not save pet: {err:#}"));
+        } else if self.pet.stage() != stage {
+            self.notice = Some(format!(
+                "Your pet evolved into a {}!",
+                self.pet.stage().name()
+            ));
         }
-        self.cached_today_stats = (total_work, total_break);
+    }
+
+    /// The pet including what today's work has done so far.
+    fn pet_now(&self) -> PetState {
+        let today = calendar().today();
+        self.pet
+            .with_today(report::day_totals(&self.sessions, &self.timeline, today, today)[0].work)
+    }
+
+    /// Steps the pet's animation once the frame's time is up, starting over
+    /// when it switches sets.
+    fn advance_animation(&mut self) {
+        let animation = Animation::select(self.get_active_session(), self.celebrating());
+        if animation != self.animation {
+            self.animation = animation;
+            self.animation_index = 0;
+            self.frame_shown = Instant::now();
+            return;
+        }
+        let frames = self.skin.frames(animation);
+        let shown_for =
+            std::time::Duration::from_millis(frames[self.animation_index % frames.len()].ms);
+        if self.frame_shown.elapsed() >= shown_for {
+            self.animation_index = (self.animation_index + 1) % frames.len();
+            self.frame_shown = Instant::now();
+        }
+    }
+
+    fn celebrating(&self) -> bool {
+        self.celebrate_until
+            .is_some_and(|until| Instant::now() < until)
     }
 
     fn start_new_session(&mut self, kind: SessionType) {
         let now = Utc::now();
-        if let Some(idx) = self.current_session_index {
-            if self.sessions[idx].end_time.is_none() {
-                self.sessions[idx].end_time = Some(now);
-            }
+        let mut events = Vec::new();
+        if let Some(idx) = self.current_session_index
+            && self.sessions[idx].end_time.is_none()
+        {
+            self.sessions[idx].end_time = Some(now);
+            events.push(LogEvent::Ended {
+                index: idx,
+                end_time: now,
+            });
         }
-        let new_session = Session {
-            start_time: now,
-            end_time: None,
-            session_type: kind,
-            note: String::new(),
-        };
+        let new_session = Session::new(kind, now);
+        events.push(LogEvent::Started {
+            session: new_session.clone(),
+        });
         self.sessions.push(new_session);
         self.current_session_index = Some(self.sessions.len() - 1);
-        save_sessions(&self.sessions).ok();
+        self.record(&events);
         self.update_stats_cache();
     }
 
     fn toggle_work_break(&mut self) {
+        if self.pomodoro.is_some() {
+            self.advance_pomodoro();
+            return;
+        }
         if let Some(idx) = self.current_session_index {
             match self.sessions[idx].session_type {
                 SessionType::Work => self.start_new_session(SessionType::Break),
-                SessionType::Break => self.start_new_session(SessionType::Work),
+                SessionType::Break => self.resume_work(),
                 SessionType::Idle => self.start_new_session(SessionType::Work),
             }
         }
     }
 
+    /// Starts work again with the project and tags of the last work session.
+    fn resume_work(&mut self) {
+        let previous = self
+            .sessions
+            .iter()
+            .rev()
+            .find(|s| s.session_type == SessionType::Work)
+            .map(|s| (s.project.clone(), s.tags.clone()));
+        self.start_new_session(SessionType::Work);
+        if let Some((project, tags)) = previous
+            && (project.is_some() || !tags.is_empty())
+        {
+            self.set_active_project(project, tags);
+        }
+    }
+
     fn stop_working(&mut self) {
-        if let Some(idx) = self.current_session_index {
-            if self.sessions[idx].session_type != SessionType::Idle {
-                self.start_new_session(SessionType::Idle);
+        self.pomodoro = None;
+        if let Some(idx) = self.current_session_index
+            && self.sessions[idx].session_type != SessionType::Idle
+        {
+            self.start_new_session(SessionType::Idle);
+        }
+    }
+
+    fn toggle_pomodoro(&mut self) {
+        if self.pomodoro.take().is_none() {
+            self.pomodoro = Some(Pomodoro::new(self.pomodoro_config));
+            self.start_new_session(SessionType::Work);
+        }
+    }
+
+    /// Closes the current Pomodoro segment and opens the one that follows it.
+    /// A new work segment carries on with the project of the last one.
+    fn advance_pomodoro(&mut self) {
+        let finished = self.get_active_session().session_type;
+        if let Some(pomodoro) = self.pomodoro.as_mut() {
+            match pomodoro.advance(finished) {
+                SessionType::Work => self.resume_work(),
+                next => self.start_new_session(next),
             }
         }
     }
 
+    /// Time left in the current Pomodoro segment, if the mode is on.
+    fn pomodoro_remaining(&self) -> Option<Duration> {
+        let active = self.get_active_session();
+        let length = self
+            .pomodoro
+            .as_ref()?
+            .segment_length(active.session_type)?;
+        Some((length - active.duration()).max(Duration::zero()))
+    }
+
     fn get_active_session(&self) -> &Session {
         &self.sessions[self.current_session_index.unwrap()]
     }
 
+    /// Indices of the sessions on `selected_date`, newest first (table order).
+    fn selected_day_indices(&self) -> Vec<usize> {
+        let mut indices = self
+            .timeline
+            .day_indices(&self.sessions, self.selected_date);
+        indices.reverse();
+        indices
+    }
+
+    /// Index into `sessions` of the row highlighted in the history table.
+    fn selected_session_index(&self) -> Option<usize> {
+        let row = self.table_state.selected()?;
+        self.selected_day_indices().get(row).copied()
+    }
+
     fn delete_selected_entry(&mut self) {
-        if let Some(table_idx) = self.table_state.selected() {
-            let date_indices: Vec<usize> = self
-                .sessions
-                .iter()
-                .enumerate()
-                .filter(|(_, s)| s.start_time_local().date_naive() == self.selected_date)
-                .map(|(i, _)| i)
-                .rev()
-                .collect();
-
-            if let Some(&real_idx) = date_indices.get(table_idx) {
-                if Some(real_idx) == self.current_session_index {
-                    return;
-                }
-                self.sessions.remove(real_idx);
-                if let Some(curr) = self.current_session_index {
-                    if real_idx < curr {
-                        self.current_session_index = Some(curr - 1);
-                    }
-                }
-                save_sessions(&self.sessions).ok();
-                self.update_stats_cache();
-                self.table_state.select(None);
+        if let Some(real_idx) = self.selected_session_index() {
+            if Some(real_idx) == self.current_session_index {
+                return;
+            }
+            self.sessions.remove(real_idx);
+            if let Some(curr) = self.current_session_index
+                && real_idx < curr
+            {
+                self.current_session_index = Some(curr - 1);
             }
+            self.record(&[LogEvent::Deleted { index: real_idx }]);
+            self.update_stats_cache();
+            self.table_state.select(None);
+        }
+    }
+
+    /// Opens the session form for a past session, or for a new one on the
+    /// selected day.
+    fn open_session_form(&mut self, idx: Option<usize>) {
+        self.session_form = Some(match idx {
+            Some(idx) => SessionForm::edit(idx, &self.sessions[idx]),
+            None => SessionForm::insert(self.selected_date),
+        });
+        self.input_mode = InputMode::EditingSession;
+    }
+
+    /// Saves the session form, or keeps it open with the reason it does not
+    /// check out.
+    fn save_session_form(&mut self) {
+        let Some(form) = &self.session_form else {
+            return;
+        };
+        match form.save(&mut self.sessions) {
+            Ok((index, events)) => {
+                self.session_form = None;
+                self.input_mode = InputMode::Normal;
+                self.history_edited(&events, index);
+            }
+            Err(err) => self.notice = Some(format!("{err:#}")),
+        }
+    }
+
+    /// Splits the session the split prompt was opened for at the time typed.
+    fn split_session(&mut self) {
+        let Some(idx) = self.editing_history_index else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+        match editor::split(&mut self.sessions, idx, &self.input_buffer) {
+            Ok(events) => {
+                self.editing_history_index = None;
+                self.input_mode = InputMode::Normal;
+                self.history_edited(&events, idx);
+            }
+            Err(err) => self.notice = Some(format!("{err:#}")),
+        }
+    }
+
+    fn merge_selected_with_next(&mut self) {
+        let Some(idx) = self.selected_session_index() else {
+            return;
+        };
+        match editor::merge_with_next(&mut self.sessions, idx) {
+            Ok(events) => self.history_edited(&events, idx),
+            Err(err) => self.notice = Some(format!("{err:#}")),
+        }
+    }
+
+    /// Saves edits to the history and selects the session at `index`.
+    fn history_edited(&mut self, events: &[LogEvent], index: usize) {
+        self.current_session_index = self.sessions.iter().rposition(|s| s.end_time.is_none());
+        self.record(events);
+        self.update_stats_cache();
+        let row = self.selected_day_indices().iter().position(|i| *i == index);
+        self.table_state.select(row);
+    }
+
+    fn session_form_key(&mut self, code: KeyCode) {
+        let Some(form) = self.session_form.as_mut() else {
+            self.input_mode = InputMode::Normal;
+            return;
+        };
+        match code {
+            KeyCode::Enter => self.save_session_form(),
+            KeyCode::Esc => {
+                self.session_form = None;
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Tab => form.next_field(),
+            KeyCode::BackTab => form.previous_field(),
+            KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right if form.field == Field::Type => {
+                form.cycle_type()
+            }
+            KeyCode::Backspace => form.pop(),
+            KeyCode::Char(c) => form.push(c),
+            _ => {}
         }
     }
 
     fn save_note(&mut self) {
-        if let Some(idx) = self.editing_history_index {
-            self.sessions[idx].note = self.input_buffer.clone();
-        } else if let Some(idx) = self.current_session_index {
-            self.sessions[idx].note = self.input_buffer.clone();
+        if let Some(idx) = self.editing_history_index.or(self.current_session_index) {
+            self.set_note(idx, self.input_buffer.clone());
+        }
+        self.editing_history_index = None;
+    }
+
+    fn set_active_note(&mut self, note: String) {
+        if let Some(idx) = self.current_session_index {
+            self.set_note(idx, note);
+        }
+    }
+
+    fn set_note(&mut self, idx: usize, note: String) {
+        self.sessions[idx].note = note.clone();
+        self.record(&[LogEvent::NoteChanged { index: idx, note }]);
+    }
+
+    fn set_active_project(&mut self, project: Option<String>, tags: Vec<String>) {
+        if let Some(idx) = self.current_session_index {
+            self.set_project(idx, project, tags);
+        }
+    }
+
+    fn set_project(&mut self, idx: usize, project: Option<String>, tags: Vec<String>) {
+        self.sessions[idx].project = project.clone();
+        self.sessions[idx].tags = tags.clone();
+        self.record(&[LogEvent::ProjectChanged {
+            index: idx,
+            project,
+            tags,
+        }]);
+    }
+
+    /// Opens the project picker for a past session, or the running one.
+    fn open_project_picker(&mut self, idx: Option<usize>) {
+        let Some(target) = idx.or(self.current_session_index) else {
+            return;
+        };
+        self.input_mode = InputMode::PickingProject;
+        self.input_buffer = self.sessions[target].project_label();
+        self.editing_history_index = idx;
+    }
+
+    fn save_project(&mut self) {
+        if let Some(idx) = self.editing_history_index.or(self.current_session_index) {
+            let (project, tags) = parse_project_label(&self.input_buffer);
+            self.set_project(idx, project, tags);
         }
-        save_sessions(&self.sessions).ok();
         self.editing_history_index = None;
     }
 
+    /// Past projects, most recently used first.
+    fn known_projects(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.sessions
+            .iter()
+            .rev()
+            .filter_map(|s| s.project.as_deref())
+            .filter(|p| seen.insert(*p))
+            .collect()
+    }
+
+    /// Past projects starting with what is typed before the first tag.
+    fn project_suggestions(&self) -> Vec<&str> {
+        let typed = self.input_buffer.split('#').next().unwrap_or_default();
+        let typed = typed.trim().to_lowercase();
+        self.known_projects()
+            .into_iter()
+            .filter(|p| {
+                let p = p.to_lowercase();
+                p.starts_with(&typed) && p != typed
+            })
+            .collect()
+    }
+
+    fn complete_project(&mut self) {
+        let Some(project) = self.project_suggestions().first().map(|p| p.to_string()) else {
+            return;
+        };
+        self.input_buffer = match self.input_buffer.find('#') {
+            Some(i) => format!("{project} {}", &self.input_buffer[i..]),
+            None => project,
+        };
+    }
+
     fn on_tick(&mut self) {
-        self.animation_index = (self.animation_index + 1) % crate::assets::FRAMES_ACTIVE.len();
-        if self.selected_date == Local::now().date_naive() {
+        self.sync_remote();
+        self.advance_animation();
+        if self.pomodoro_remaining() == Some(Duration::zero()) {
+            self.advance_pomodoro();
+        }
+        let today = calendar().today();
+        if self.selected_date == today {
             self.update_stats_cache();
         }
+        self.update_pet();
+        let met = self.goal_met(today) == Some(true);
+        if met && !self.goal_met_today {
+            self.celebrate_until = Some(Instant::now() + CELEBRATION);
+            self.notice = Some("Daily goal reached!".to_string());
+        }
+        self.goal_met_today = met;
+        self.check_reminders();
+        match self.idle.poll(Utc::now()) {
+            Ok(Some(away)) => self.came_back(away),
+            Ok(None) => {}
+            Err(err) => {
+                self.notice = Some(format!("{err:#}, using terminal focus instead"));
+            }
+        }
+    }
+
+    /// Asks what to do with time away if it was taken out of a work session.
+    fn came_back(&mut self, away: Away) {
+        let session = self.get_active_session();
+        if session.session_type == SessionType::Work && away.until > session.start_time {
+            self.away = Some(away);
+        }
+    }
+
+    /// Turns the time away from the running work session into `kind`.
+    fn split_away(&mut self, away: Away, kind: SessionType) {
+        let Some(idx) = self.current_session_index else {
+            return;
+        };
+        let session = &self.sessions[idx];
+        if session.session_type != SessionType::Work || session.end_time.is_some() {
+            return;
+        }
+        let events = idle::split_work(&mut self.sessions, idx, away, kind, Utc::now());
+        self.current_session_index = Some(self.sessions.len() - 1);
+        self.record(&events);
+        self.update_stats_cache();
+    }
+
+    /// Keys of the came-back prompt, which takes every key while it is open.
+    /// Returns false if there is none.
+    fn away_key(&mut self, code: KeyCode) -> bool {
+        let Some(away) = self.away else {
+            return false;
+        };
+        let kind = match code {
+            KeyCode::Char('k') | KeyCode::Enter | KeyCode::Esc => None,
+            KeyCode::Char('b') => Some(SessionType::Break),
+            KeyCode::Char('d') => Some(SessionType::Idle),
+            _ => return true,
+        };
+        self.away = None;
+        if let Some(kind) = kind {
+            self.split_away(away, kind);
+        }
+        true
+    }
+
+    /// Closes the key help on any key. Returns false if it is not open.
+    fn close_key_help(&mut self) -> bool {
+        std::mem::take(&mut self.show_keys)
+    }
+
+    /// Pops up the next due reminder, ringing the bell and running the hook.
+    fn check_reminders(&mut self) {
+        if self.reminder.is_some() {
+            return;
+        }
+        let session = &self.sessions[self.current_session_index.unwrap()];
+        let Some(popup) = self.reminders.check(session, Utc::now()) else {
+            return;
+        };
+        if self.reminders.config.bell {
+            let _ = execute!(io::stdout(), Print('\u{7}'));
+        }
+        if let Err(err) = self.reminders.run_hook(&popup.message) {
+            self.notice = Some(format!("{err:#}"));
+        }
+        self.reminder = Some(popup);
+    }
+
+    /// Keys of the reminder popup, which takes every key while it is open.
+    /// Returns false if there is none.
+    fn reminder_key(&mut self, code: KeyCode) -> bool {
+        let Some(popup) = &self.reminder else {
+            return false;
+        };
+        match code {
+            KeyCode::Char('z') => {
+                self.reminders.snooze(popup, Utc::now());
+                self.reminder = None;
+            }
+            KeyCode::Enter | KeyCode::Esc => self.reminder = None,
+            KeyCode::Char(' ') => {
+                self.reminder = None;
+                self.toggle_work_break();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Writes the sessions of `selected_date` to the export directory.
+    fn export_selected_day(&mut self, format: ExportFormat) {
+        let options = ExportOptions::for_day(format, self.selected_date);
+        let sessions = options.select(
+            self.timeline
+                .day_indices(&self.sessions, self.selected_date)
+                .into_iter()
+                .map(|i| &self.sessions[i]),
+        );
+        self.notice = Some(
+            match export::export_to_dir(&self.export_dir, &options, &sessions) {
+                Ok(path) => format!("Exported {} sessions to {}", sessions.len(), path.display()),
+                Err(err) => format!("Export failed: {err:#}"),
+            },
+        );
+    }
+
+    fn toggle_report(&mut self) {
+        self.view = match self.view {
+            View::Report(_) => View::History,
+            _ => View::Report(ReportView::new(self.selected_date)),
+        };
+    }
+
+    fn toggle_heatmap(&mut self) {
+        self.view = match self.view {
+            View::Heatmap(_) => View::History,
+            _ => View::Heatmap(HeatmapView::new(self.selected_date)),
+        };
+    }
+
+    /// Handles the keys that mean something else on the report and heatmap
+    /// screens. Returns false if `code` should get its usual meaning.
+    fn view_key(&mut self, code: KeyCode) -> bool {
+        match (&mut self.view, code) {
+            (View::Report(report), KeyCode::Left) => report.page(-1),
+            (View::Report(report), KeyCode::Right) => report.page(1),
+            (View::Report(report), KeyCode::Tab) => report.toggle_period(),
+            (View::Heatmap(map), KeyCode::Left) => map.move_cursor(-7),
+            (View::Heatmap(map), KeyCode::Right) => map.move_cursor(7),
+            (View::Heatmap(map), KeyCode::Up) => map.move_cursor(-1),
+            (View::Heatmap(map), KeyCode::Down) => map.move_cursor(1),
+            (View::Heatmap(map), KeyCode::Enter) => {
+                let date = map.cursor;
+                self.view = View::History;
+                self.change_date((date - self.selected_date).num_days());
+            }
+            (View::Report(_) | View::Heatmap(_), KeyCode::Esc) => self.view = View::History,
+            _ => return false,
+        }
+        true
     }
 
     fn change_date(&mut self, days: i64) {
-        self.selected_date = self.selected_date + Duration::days(days);
+        self.selected_date += Duration::days(days);
         self.table_state.select(None);
         self.update_stats_cache();
     }
 }
 
 fn main() -> Result<()> {
+    let cli = CliArgs::parse(std::env::args().skip(1))?;
+    let mut config = Config::load(&cli)?;
+    let (data_path, is_default) = config.resolve_data_file(&cli);
+    set_calendar(config.calendar()?);
+    config.export_dir = Some(config.resolve_export_dir(&data_path));
+    config.pet_file = Some(config.resolve_pet_file(&data_path));
+    if let Command::ValidateSkin { skin } = &cli.command {
+        return skin::validate(&config, skin);
+    }
+    if is_default {
+        migrate_legacy_log(&data_path)?;
+    }
+    #[cfg(unix)]
+    let store = {
+        let socket = config.resolve_socket(&data_path);
+        if let Command::Daemon = cli.command {
+            return daemon::run(&config, config.open_store(data_path)?, &socket);
+        }
+        match daemon::RemoteStore::connect(&socket)? {
+            Some(remote) => Box::new(remote),
+            None => config.open_store(data_path)?,
+        }
+    };
+    #[cfg(not(unix))]
+    let store = config.open_store(data_path)?;
+
+    match cli.command {
+        Command::Tui => run_tui(App::new(&config, store)?),
+        Command::Status { json } => cli::print_status(store.as_ref(), json),
+        Command::Export(options) => export::run(store.as_ref(), &options),
+        Command::Import(options) => import::run(store, &options),
+        Command::Log(options) => editor::run_log(store, options),
+        Command::Daemon => anyhow::bail!("the daemon needs Unix domain sockets"),
+        command => cli::run(&config, store, command),
+    }
+}
+
+fn run_tui(mut app: App) -> Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableFocusChange
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
     let tick_rate = std::time::Duration::from_millis(200);
     let mut last_tick = Instant::now();
 
@@ -191,109 +896,163 @@ fn main() -> Result<()> {
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or(std::time::Duration::ZERO);
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match app.input_mode {
-                        InputMode::Normal => match key.code {
-                            KeyCode::Char('q') => break,
-                            KeyCode::Char(' ') => app.toggle_work_break(),
-                            KeyCode::Char('s') => app.stop_working(),
-                            KeyCode::Left => app.change_date(-1),
-                            KeyCode::Right => app.change_date(1),
-                            KeyCode::Down => {
-                                let i = match app.table_state.selected() {
-                                    Some(i) => {
-                                        let count = app
-                                            .sessions
-                                            .iter()
-                                            .filter(|s| {
-                                                s.start_time_local().date_naive()
-                                                    == app.selected_date
-                                            })
-                                            .count();
-                                        if count == 0 {
-                                            0
-                                        } else if i >= count - 1 {
-                                            0
-                                        } else {
-                                            i + 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app.table_state.select(Some(i));
-                            }
-                            KeyCode::Up => {
-                                let i = match app.table_state.selected() {
-                                    Some(i) => {
-                                        let count = app
-                                            .sessions
-                                            .iter()
-                                            .filter(|s| {
-                                                s.start_time_local().date_naive()
-                                                    == app.selected_date
-                                            })
-                                            .count();
-                                        if count == 0 {
-                                            0
-                                        } else if i == 0 {
-                                            count - 1
-                                        } else {
-                                            i - 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app.table_state.select(Some(i));
-                            }
-                            KeyCode::Esc => app.table_state.select(None),
-                            KeyCode::Char('d') => app.delete_selected_entry(),
-                            KeyCode::Char('n') => {
-                                app.input_mode = InputMode::EditingNote;
-                                app.input_buffer = app.get_active_session().note.clone();
-                                app.editing_history_index = None;
-                            }
-                            KeyCode::Enter => {
-                                if let Some(selected_idx) = app.table_state.selected() {
-                                    let date_indices: Vec<usize> = app
-                                        .sessions
-                                        .iter()
-                                        .enumerate()
-                                        .filter(|(_, s)| {
-                                            s.start_time_local().date_naive() == app.selected_date
-                                        })
-                                        .map(|(i, _)| i)
-                                        .rev()
-                                        .collect();
-                                    if let Some(&real_idx) = date_indices.get(selected_idx) {
-                                        app.input_mode = InputMode::EditingNote;
-                                        app.input_buffer = app.sessions[real_idx].note.clone();
-                                        app.editing_history_index = Some(real_idx);
-                                    }
+        let event = if crossterm::event::poll(timeout)? {
+            Some(event::read()?)
+        } else {
+            None
+        };
+        match event {
+            Some(Event::FocusLost) => app.idle.focus_lost(Utc::now()),
+            Some(Event::FocusGained) => {
+                if let Some(away) = app.idle.focus_gained(Utc::now()) {
+                    app.came_back(away);
+                }
+            }
+            _ => {}
+        }
+        if let Some(Event::Key(key)) = event
+            && key.kind == KeyEventKind::Press
+        {
+            app.notice = None;
+            // Act on the latest state if another client changed it meanwhile
+            app.sync_remote();
+            // The came-back prompt, reminders and key help take every key; the report and heatmap screens
+            // take the arrows, Enter and Esc
+            let handled = app.away_key(key.code)
+                || app.reminder_key(key.code)
+                || app.close_key_help()
+                || (app.input_mode == InputMode::Normal && app.view_key(key.code));
+            match app.input_mode {
+                _ if handled => {}
+                InputMode::Normal => match key.code {
+                    KeyCode::Char('q') => break,
+                    KeyCode::Char(' ') => app.toggle_work_break(),
+                    KeyCode::Char('?') => app.show_keys = true,
+                    KeyCode::Char('s') => app.stop_working(),
+                    KeyCode::Char('p') => app.toggle_pomodoro(),
+                    KeyCode::Char('r') => app.toggle_report(),
+                    KeyCode::Char('h') => app.toggle_heatmap(),
+                    KeyCode::Left => app.change_date(-1),
+                    KeyCode::Right => app.change_date(1),
+                    KeyCode::Down => {
+                        let i = match app.table_state.selected() {
+                            Some(i) => {
+                                let count = app
+                                    .timeline
+                                    .day_indices(&app.sessions, app.selected_date)
+                                    .len();
+                                if count == 0 || i >= count - 1 {
+                                    0
+                                } else {
+                                    i + 1
                                 }
                             }
-                            _ => {}
-                        },
-                        InputMode::EditingNote => match key.code {
-                            KeyCode::Enter => {
-                                app.save_note();
-                                app.input_mode = InputMode::Normal;
-                            }
-                            KeyCode::Esc => {
-                                app.input_mode = InputMode::Normal;
-                                app.editing_history_index = None;
-                            }
-                            KeyCode::Backspace => {
-                                app.input_buffer.pop();
-                            }
-                            KeyCode::Char(c) => {
-                                app.input_buffer.push(c);
+                            None => 0,
+                        };
+                        app.table_state.select(Some(i));
+                    }
+                    KeyCode::Up => {
+                        let i = match app.table_state.selected() {
+                            Some(i) => {
+                                let count = app
+                                    .timeline
+                                    .day_indices(&app.sessions, app.selected_date)
+                                    .len();
+                                if count == 0 {
+                                    0
+                                } else if i == 0 {
+                                    count - 1
+                                } else {
+                                    i - 1
+                                }
                             }
-                            _ => {}
-                        },
+                            None => 0,
+                        };
+                        app.table_state.select(Some(i));
                     }
-                }
+                    KeyCode::Esc => app.table_state.select(None),
+                    KeyCode::Char('d') => app.delete_selected_entry(),
+                    KeyCode::Char('e') => app.export_selected_day(ExportFormat::Csv),
+                    KeyCode::Char('i') => app.export_selected_day(ExportFormat::Ics),
+                    KeyCode::Char('t') => app.open_project_picker(app.selected_session_index()),
+                    KeyCode::Char('a') => app.open_session_form(None),
+                    KeyCode::Char('m') => {
+                        if let Some(real_idx) = app.selected_session_index() {
+                            app.open_session_form(Some(real_idx));
+                        }
+                    }
+                    KeyCode::Char('x') => {
+                        if let Some(real_idx) = app.selected_session_index() {
+                            app.input_mode = InputMode::SplittingSession;
+                            app.input_buffer.clear();
+                            app.editing_history_index = Some(real_idx);
+                        }
+                    }
+                    KeyCode::Char('j') => app.merge_selected_with_next(),
+                    KeyCode::Char('n') => {
+                        app.input_mode = InputMode::EditingNote;
+                        app.input_buffer = app.get_active_session().note.clone();
+                        app.editing_history_index = None;
+                    }
+                    KeyCode::Enter => {
+                        if let Some(real_idx) = app.selected_session_index() {
+                            app.input_mode = InputMode::EditingNote;
+                            app.input_buffer = app.sessions[real_idx].note.clone();
+                            app.editing_history_index = Some(real_idx);
+                        }
+                    }
+                    _ => {}
+                },
+                InputMode::EditingNote => match key.code {
+                    KeyCode::Enter => {
+                        app.save_note();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                        app.editing_history_index = None;
+                    }
+                    KeyCode::Backspace => {
+                        app.input_buffer.pop();
+                    }
+                    KeyCode::Char(c) => {
+                        app.input_buffer.push(c);
+                    }
+                    _ => {}
+                },
+                InputMode::PickingProject => match key.code {
+                    KeyCode::Enter => {
+                        app.save_project();
+                        app.input_mode = InputMode::Normal;
+                    }
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                        app.editing_history_index = None;
+                    }
+                    KeyCode::Tab => app.complete_project(),
+                    KeyCode::Backspace => {
+                        app.input_buffer.pop();
+                    }
+                    KeyCode::Char(c) => {
+                        app.input_buffer.push(c);
+                    }
+                    _ => {}
+                },
+                InputMode::EditingSession => app.session_form_key(key.code),
+                InputMode::SplittingSession => match key.code {
+                    KeyCode::Enter => app.split_session(),
+                    KeyCode::Esc => {
+                        app.input_mode = InputMode::Normal;
+                        app.editing_history_index = None;
+                    }
+                    KeyCode::Backspace => {
+                        app.input_buffer.pop();
+                    }
+                    KeyCode::Char(c) => {
+                        app.input_buffer.push(c);
+                    }
+                    _ => {}
+                },
             }
         }
         if last_tick.elapsed() >= tick_rate {
@@ -306,7 +1065,8 @@ fn main() -> Result<()> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableFocusChange
     )?;
     terminal.show_cursor()?;
     Ok(())
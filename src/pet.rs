@@ -0,0 +1,247 @@
+use crate::calendar::calendar;
+use crate::data::{Session, Timeline, load_with_recovery, write_atomic};
+use crate::goals::GoalConfig;
+use crate::report::day_totals;
+use anyhow::Result;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Share of a worked day spent on breaks below which the pet gets tired.
+const MIN_BREAK_SHARE: f64 = 0.1;
+/// Work beyond this many hours a day drains energy.
+const LONG_DAY_HOURS: f64 = 8.0;
+
+/// Evolution stages, unlocked by level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Egg,
+    Spark,
+    Heart,
+    Crowned,
+}
+
+impl Stage {
+    pub fn name(&self) -> &str {
+        match self {
+            Stage::Egg => "Egg",
+            Stage::Spark => "Spark",
+            Stage::Heart => "Heart",
+            Stage::Crowned => "Crowned Heart",
+        }
+    }
+}
+
+/// The pet's needs (0-100) and experience. Days are folded in once they
+/// are over, so the state only depends on the session history and two
+/// instances sharing a log arrive at the same pet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PetState {
+    pub hunger: i32,
+    pub energy: i32,
+    pub happiness: i32,
+    pub xp: u32,
+    /// Last day whose sessions are already counted.
+    pub counted_until: Option<NaiveDate>,
+}
+
+impl Default for PetState {
+    fn default() -> Self {
+        PetState {
+            hunger: 30,
+            energy: 70,
+            happiness: 60,
+            xp: 0,
+            counted_until: None,
+        }
+    }
+}
+
+impl PetState {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(load_with_recovery(path)?.0)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(path, &serde_json::to_vec_pretty(self)?, 0)
+    }
+
+    /// Level 2 at 25 XP, 3 at 100, 10 at 2025.
+    pub fn level(&self) -> u32 {
+        (self.xp as f64 / 25.0).sqrt() as u32 + 1
+    }
+
+    pub fn stage(&self) -> Stage {
+        match self.level() {
+            0..=2 => Stage::Egg,
+            3..=5 => Stage::Spark,
+            6..=9 => Stage::Heart,
+            _ => Stage::Crowned,
+        }
+    }
+
+    /// One word for the most pressing need.
+    pub fn mood(&self) -> &str {
+        if self.hunger >= 80 {
+            "starving"
+        } else if self.energy <= 20 {
+            "exhausted"
+        } else if self.happiness <= 25 {
+            "sad"
+        } else if self.hunger >= 60 {
+            "hungry"
+        } else if self.energy <= 40 {
+            "tired"
+        } else if self.happiness >= 75 {
+            "happy"
+        } else {
+            "content"
+        }
+    }
+
+    /// Folds every finished day since the last call into the state. Returns
+    /// whether anything changed.
+    pub fn catch_up(
+        &mut self,
+        sessions: &[Session],
+        timeline: &Timeline,
+        goals: &GoalConfig,
+        today: NaiveDate,
+    ) -> bool {
+        let Some(first_day) = sessions.first().map(|s| calendar().date_of(s.start_time)) else {
+            return false;
+        };
+        let first = self
+            .counted_until
+            .map_or(first_day, |d| d + Duration::days(1));
+        let last = today - Duration::days(1);
+        if first > last {
+            return false;
+        }
+        for day in day_totals(sessions, timeline, first, last) {
+            self.end_day(day.work, day.rest, goals.met(day.date, day.work));
+        }
+        self.counted_until = Some(last);
+        true
+    }
+
+    /// Work feeds the pet, breaks and days off restore its energy, and
+    /// steady days that meet the goal make it happy.
+    fn end_day(&mut self, work: Duration, rest: Duration, goal_met: Option<bool>) {
+        let hours = work.num_minutes() as f64 / 60.0;
+        let total = (work + rest).num_seconds();
+        let break_share = if total > 0 {
+            rest.num_seconds() as f64 / total as f64
+        } else {
+            0.0
+        };
+        let balanced = work > Duration::zero() && break_share >= MIN_BREAK_SHARE;
+
+        self.hunger += 30 - (hours * 10.0).min(60.0) as i32;
+
+        if work <= Duration::zero() {
+            self.energy += 25;
+        } else {
+            self.energy += if balanced { 15 } else { -10 };
+            if hours > LONG_DAY_HOURS {
+                self.energy -= ((hours - LONG_DAY_HOURS) * 10.0) as i32;
+            }
+        }
+
+        if balanced {
+            self.happiness += 10;
+        }
+        match goal_met {
+            Some(true) => self.happiness += 10,
+            Some(false) => self.happiness -= 10,
+            None => {}
+        }
+        if self.hunger > 70 {
+            self.happiness -= 10;
+        }
+        if self.energy < 30 {
+            self.happiness -= 10;
+        }
+
+        self.hunger = self.hunger.clamp(0, 100);
+        self.energy = self.energy.clamp(0, 100);
+        self.happiness = self.happiness.clamp(0, 100);
+        self.xp += (work.num_minutes() / 5).max(0) as u32;
+        if goal_met == Some(true) {
+            self.xp += 20;
+        }
+    }
+
+    /// The state as it looks right now: today's work already feeds the pet
+    /// and earns XP, the rest is settled when the day is over.
+    pub fn with_today(&self, work: Duration) -> PetState {
+        let hours = work.num_minutes() as f64 / 60.0;
+        PetState {
+            hunger: (self.hunger - (hours * 10.0).min(60.0) as i32).max(0),
+            xp: self.xp + (work.num_minutes() / 5).max(0) as u32,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SessionType::{Break, Work};
+    use crate::testing::{date, logged};
+
+    fn needs(pet: &PetState) -> (i32, i32, i32, u32) {
+        (pet.hunger, pet.energy, pet.happiness, pet.xp)
+    }
+
+    #[test]
+    fn balanced_days_that_meet_the_goal_feed_and_please_the_pet() {
+        let mut pet = PetState::default();
+        pet.end_day(Duration::hours(6), Duration::hours(1), Some(true));
+        assert_eq!(needs(&pet), (0, 85, 80, 92));
+        assert_eq!(pet.mood(), "happy");
+        assert_eq!((pet.level(), pet.stage()), (2, Stage::Egg));
+    }
+
+    #[test]
+    fn catching_up_folds_in_every_day_skipped_since() {
+        let goals = GoalConfig {
+            hours: Some(6.0),
+            sat: Some(0.0),
+            sun: Some(0.0),
+            ..GoalConfig::default()
+        };
+        // Monday and Thursday are worked, the goal is missed on the days between
+        let sessions = vec![
+            logged(Work, "2024-05-13", "09:00:00", "15:00:00"),
+            logged(Break, "2024-05-13", "15:00:00", "16:00:00"),
+            logged(Work, "2024-05-16", "08:00:00", "18:00:00"),
+        ];
+        let timeline = Timeline::new(&sessions);
+        let mut pet = PetState::default();
+
+        assert!(pet.catch_up(&sessions, &timeline, &goals, date("2024-05-18")));
+        assert_eq!(pet.counted_until, Some(date("2024-05-17")));
+        // Thursday's ten hours without a break cost energy, Friday restores it
+        assert_eq!(needs(&pet), (60, 95, 60, 232));
+        assert_eq!(pet.mood(), "hungry");
+        assert_eq!((pet.level(), pet.stage()), (4, Stage::Spark));
+
+        // Days are only counted once
+        assert!(!pet.catch_up(&sessions, &timeline, &goals, date("2024-05-18")));
+        assert_eq!(needs(&pet), (60, 95, 60, 232));
+
+        // The weekend is off: no goal to miss, but the pet still gets hungry
+        assert!(pet.catch_up(&sessions, &timeline, &goals, date("2024-05-20")));
+        assert_eq!(needs(&pet), (100, 100, 40, 232));
+        assert_eq!(pet.mood(), "starving");
+    }
+
+    #[test]
+    fn todays_work_shows_before_the_day_is_over() {
+        let pet = PetState::default().with_today(Duration::hours(2));
+        assert_eq!(needs(&pet), (10, 70, 60, 24));
+        assert_eq!(pet.counted_until, None);
+    }
+}
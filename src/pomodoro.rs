@@ -0,0 +1,166 @@
+use crate::data::SessionType;
+use anyhow::{Result, bail};
+use chrono::Duration;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    pub work_minutes: i64,
+    pub short_break_minutes: i64,
+    pub long_break_minutes: i64,
+    pub long_break_every: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        PomodoroConfig {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+impl PomodoroConfig {
+    /// Rejects lengths that would make every segment due at once.
+    pub fn validate(&self) -> Result<()> {
+        for (name, minutes) in [
+            ("work_minutes", self.work_minutes),
+            ("short_break_minutes", self.short_break_minutes),
+            ("long_break_minutes", self.long_break_minutes),
+        ] {
+            if minutes <= 0 {
+                bail!("pomodoro.{name} must be at least 1, not {minutes}");
+            }
+        }
+        if self.long_break_every == 0 {
+            bail!("pomodoro.long_break_every must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+/// Tracks where we are in the work/break cycle while Pomodoro mode is on.
+#[derive(Debug, Clone)]
+pub struct Pomodoro {
+    pub config: PomodoroConfig,
+    pub cycle: u32,
+    pub long_break: bool,
+}
+
+impl Pomodoro {
+    pub fn new(config: PomodoroConfig) -> Self {
+        Pomodoro {
+            config,
+            cycle: 1,
+            long_break: false,
+        }
+    }
+
+    /// Planned length of a segment of the given type, `None` for Idle.
+    pub fn segment_length(&self, kind: SessionType) -> Option<Duration> {
+        match kind {
+            SessionType::Work => Some(Duration::minutes(self.config.work_minutes)),
+            SessionType::Break if self.long_break => {
+                Some(Duration::minutes(self.config.long_break_minutes))
+            }
+            SessionType::Break => Some(Duration::minutes(self.config.short_break_minutes)),
+            SessionType::Idle => None,
+        }
+    }
+
+    /// Moves the cycle forward after a segment of type `finished` and returns
+    /// the type of the segment that should follow it.
+    pub fn advance(&mut self, finished: SessionType) -> SessionType {
+        match finished {
+            SessionType::Work => {
+                self.long_break = self.cycle.is_multiple_of(self.config.long_break_every);
+                SessionType::Break
+            }
+            SessionType::Break => {
+                self.cycle += 1;
+                self.long_break = false;
+                SessionType::Work
+            }
+            SessionType::Idle => SessionType::Work,
+        }
+    }
+
+    pub fn label(&self, kind: SessionType) -> String {
+        match kind {
+            SessionType::Break if self.long_break => format!("LONG BREAK #{}", self.cycle),
+            _ => format!("{} #{}", kind.label(), self.cycle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn every(long_break_every: u32) -> PomodoroConfig {
+        PomodoroConfig {
+            long_break_every,
+            ..PomodoroConfig::default()
+        }
+    }
+
+    #[test]
+    fn every_nth_break_is_long() {
+        let mut pomodoro = Pomodoro::new(every(2));
+        let mut breaks = Vec::new();
+        for _ in 0..4 {
+            assert_eq!(pomodoro.advance(SessionType::Work), SessionType::Break);
+            breaks.push((pomodoro.cycle, pomodoro.long_break));
+            assert_eq!(pomodoro.advance(SessionType::Break), SessionType::Work);
+            assert!(!pomodoro.long_break);
+        }
+        assert_eq!(breaks, [(1, false), (2, true), (3, false), (4, true)]);
+        assert_eq!(pomodoro.cycle, 5);
+    }
+
+    #[test]
+    fn segment_lengths_follow_the_cycle() {
+        let mut pomodoro = Pomodoro::new(every(1));
+        assert_eq!(
+            pomodoro.segment_length(SessionType::Work),
+            Some(Duration::minutes(25))
+        );
+        pomodoro.advance(SessionType::Work);
+        assert_eq!(
+            pomodoro.segment_length(SessionType::Break),
+            Some(Duration::minutes(15))
+        );
+        pomodoro.advance(SessionType::Break);
+        assert_eq!(
+            pomodoro.segment_length(SessionType::Break),
+            Some(Duration::minutes(5))
+        );
+        assert_eq!(pomodoro.segment_length(SessionType::Idle), None);
+    }
+
+    #[test]
+    fn idle_resumes_with_work_in_the_same_cycle() {
+        let mut pomodoro = Pomodoro::new(PomodoroConfig::default());
+        assert_eq!(pomodoro.advance(SessionType::Idle), SessionType::Work);
+        assert_eq!(pomodoro.cycle, 1);
+    }
+
+    #[test]
+    fn rejects_empty_segments() {
+        assert!(PomodoroConfig::default().validate().is_ok());
+        let zero_work = PomodoroConfig {
+            work_minutes: 0,
+            ..PomodoroConfig::default()
+        };
+        assert!(zero_work.validate().is_err());
+        let negative_break = PomodoroConfig {
+            short_break_minutes: -5,
+            ..PomodoroConfig::default()
+        };
+        assert!(negative_break.validate().is_err());
+        assert!(every(0).validate().is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::data::{Session, SessionType};
+use crate::ui::format_duration_str;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::process::{Command, Stdio};
+
+/// Environment variable the hook command finds the reminder text in.
+pub const MESSAGE_ENV: &str = "PET_TIMER_MESSAGE";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ReminderConfig {
+    pub rules: Vec<Rule>,
+    pub snooze_minutes: i64,
+    /// Ring the terminal bell when a reminder pops up.
+    pub bell: bool,
+    /// Shell command run for every reminder, e.g. `notify-send`.
+    pub hook: Option<String>,
+}
+
+impl Default for ReminderConfig {
+    fn default() -> Self {
+        ReminderConfig {
+            rules: Vec::new(),
+            snooze_minutes: 10,
+            bell: true,
+            hook: None,
+        }
+    }
+}
+
+/// Fires once a work session or a break has run for `minutes`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub kind: RuleKind,
+    pub minutes: i64,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    Work,
+    Break,
+}
+
+impl RuleKind {
+    fn matches(&self, kind: SessionType) -> bool {
+        matches!(
+            (self, kind),
+            (RuleKind::Work, SessionType::Work) | (RuleKind::Break, SessionType::Break)
+        )
+    }
+}
+
+/// A reminder waiting to be dismissed or snoozed.
+#[derive(Debug, Clone)]
+pub struct Popup {
+    pub rule: usize,
+    pub message: String,
+}
+
+/// Checks the rules against the running session. Every rule fires at most
+/// once per session unless it is snoozed.
+pub struct Reminders {
+    pub config: ReminderConfig,
+    /// Start of the session `due` belongs to.
+    session: Option<DateTime<Utc>>,
+    /// When each rule fires next, `None` if it does not apply or already fired.
+    due: Vec<Option<DateTime<Utc>>>,
+}
+
+impl Reminders {
+    pub fn new(config: ReminderConfig) -> Self {
+        Reminders {
+            due: vec![None; config.rules.len()],
+            config,
+            session: None,
+        }
+    }
+
+    /// Returns the reminder that is due for `session`, if any.
+    pub fn check(&mut self, session: &Session, now: DateTime<Utc>) -> Option<Popup> {
+        if self.session != Some(session.start_time) {
+            self.session = Some(session.start_time);
+            for (due, rule) in self.due.iter_mut().zip(&self.config.rules) {
+                *due = rule
+                    .kind
+                    .matches(session.session_type)
+                    .then(|| session.start_time + Duration::minutes(rule.minutes));
+            }
+        }
+        let rule = self
+            .due
+            .iter()
+            .position(|due| due.is_some_and(|due| due <= now))?;
+        self.due[rule] = None;
+        Some(Popup {
+            rule,
+            message: self.message(rule, session.duration()),
+        })
+    }
+
+    /// Shows the reminder again after `snooze_minutes`.
+    pub fn snooze(&mut self, popup: &Popup, now: DateTime<Utc>) {
+        self.due[popup.rule] = Some(now + Duration::minutes(self.config.snooze_minutes.max(1)));
+    }
+
+    fn message(&self, rule: usize, elapsed: Duration) -> String {
+        let rule = &self.config.rules[rule];
+        if let Some(message) = &rule.message {
+            return message.clone();
+        }
+        let elapsed = format_duration_str(elapsed);
+        match rule.kind {
+            RuleKind::Work => format!("You have been working for {elapsed}. Time for a break?"),
+            RuleKind::Break => format!("Your break has lasted {elapsed}. Back to work?"),
+        }
+    }
+
+    /// Starts the hook command in the background with the message in
+    /// `$PET_TIMER_MESSAGE`.
+    pub fn run_hook(&self, message: &str) -> Result<()> {
+        let Some(hook) = &self.config.hook else {
+            return Ok(());
+        };
+        let mut child = shell_command(hook)
+            .env(MESSAGE_ENV, message)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("running reminder hook {hook:?}"))?;
+        // Reap it without holding up the UI
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+/// `command` run by the platform's shell.
+pub fn shell_command(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{session, utc};
+
+    fn reminders() -> Reminders {
+        let rule = |kind, minutes, message: &str| Rule {
+            kind,
+            minutes,
+            message: Some(message.to_string()),
+        };
+        Reminders::new(ReminderConfig {
+            rules: vec![
+                rule(RuleKind::Work, 50, "stretch"),
+                rule(RuleKind::Break, 10, "back"),
+            ],
+            snooze_minutes: 5,
+            ..ReminderConfig::default()
+        })
+    }
+
+    fn fired(popup: Option<Popup>) -> Option<String> {
+        popup.map(|popup| popup.message)
+    }
+
+    #[test]
+    fn rules_fire_once_per_session() {
+        let mut reminders = reminders();
+        let work = session(SessionType::Work, "2024-05-06T09:00:00Z", None);
+        assert_eq!(
+            fired(reminders.check(&work, utc("2024-05-06T09:49:00Z"))),
+            None
+        );
+        assert_eq!(
+            fired(reminders.check(&work, utc("2024-05-06T09:50:00Z"))),
+            Some("stretch".into())
+        );
+        assert_eq!(
+            fired(reminders.check(&work, utc("2024-05-06T11:00:00Z"))),
+            None
+        );
+
+        // A new session starts the rules over, and only those of its kind apply
+        let rest = session(SessionType::Break, "2024-05-06T11:00:00Z", None);
+        assert_eq!(
+            fired(reminders.check(&rest, utc("2024-05-06T11:09:00Z"))),
+            None
+        );
+        assert_eq!(
+            fired(reminders.check(&rest, utc("2024-05-06T12:00:00Z"))),
+            Some("back".into())
+        );
+        let work = session(SessionType::Work, "2024-05-06T12:00:00Z", None);
+        assert_eq!(
+            fired(reminders.check(&work, utc("2024-05-06T12:50:00Z"))),
+            Some("stretch".into())
+        );
+    }
+
+    #[test]
+    fn snoozed_reminders_fire_again_later() {
+        let mut reminders = reminders();
+        let work = session(SessionType::Work, "2024-05-06T09:00:00Z", None);
+        let popup = reminders.check(&work, utc("2024-05-06T09:50:00Z")).unwrap();
+        reminders.snooze(&popup, utc("2024-05-06T09:51:00Z"));
+        assert_eq!(
+            fired(reminders.check(&work, utc("2024-05-06T09:55:00Z"))),
+            None
+        );
+        let again = reminders.check(&work, utc("2024-05-06T09:56:00Z")).unwrap();
+        assert_eq!(again.rule, popup.rule);
+        assert_eq!(
+            fired(reminders.check(&work, utc("2024-05-06T10:30:00Z"))),
+            None
+        );
+    }
+
+    #[test]
+    fn default_messages_tell_the_elapsed_time() {
+        let mut reminders = reminders();
+        reminders.config.rules[0].message = None;
+        let work = session(
+            SessionType::Work,
+            "2024-05-06T09:00:00Z",
+            Some("2024-05-06T10:30:00Z"),
+        );
+        let popup = reminders.check(&work, utc("2024-05-06T10:30:00Z")).unwrap();
+        assert_eq!(
+            popup.message,
+            format!(
+                "You have been working for {}. Time for a break?",
+                format_duration_str(Duration::minutes(90))
+            )
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use crate::data::{Session, SessionType, Timeline, local_day_bounds};
+use chrono::{Datelike, Duration, Months, NaiveDate};
+
+/// Back-to-back work sessions closer than this count as one focus stretch.
+const STREAK_GAP: Duration = Duration::minutes(1);
+
+/// Columns of the heatmap, enough for a full year whatever day it starts.
+pub const HEATMAP_WEEKS: i64 = 53;
+
+/// Monday of the ISO week `date` falls in.
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Week,
+    Month,
+}
+
+/// The week or month shown on the report screen.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportView {
+    pub period: Period,
+    /// Any day inside the period.
+    pub anchor: NaiveDate,
+}
+
+impl ReportView {
+    pub fn new(anchor: NaiveDate) -> Self {
+        ReportView {
+            period: Period::Week,
+            anchor,
+        }
+    }
+
+    /// First and last day of the ISO week or calendar month.
+    pub fn bounds(&self) -> (NaiveDate, NaiveDate) {
+        match self.period {
+            Period::Week => {
+                let first = week_start(self.anchor);
+                (first, first + Duration::days(6))
+            }
+            Period::Month => {
+                let first = self.anchor.with_day(1).unwrap();
+                (first, first + Months::new(1) - Duration::days(1))
+            }
+        }
+    }
+
+    pub fn title(&self) -> String {
+        let (first, last) = self.bounds();
+        match self.period {
+            Period::Week => {
+                let week = self.anchor.iso_week();
+                format!(
+                    "Week {}, {} ({} - {})",
+                    week.week(),
+                    week.year(),
+                    first.format("%b %d"),
+                    last.format("%b %d")
+                )
+            }
+            Period::Month => self.anchor.format("%B %Y").to_string(),
+        }
+    }
+
+    /// Moves by whole weeks or months.
+    pub fn page(&mut self, delta: i32) {
+        self.anchor = match self.period {
+            Period::Week => self.anchor + Duration::weeks(delta as i64),
+            Period::Month if delta < 0 => self.anchor - Months::new(delta.unsigned_abs()),
+            Period::Month => self.anchor + Months::new(delta as u32),
+        };
+    }
+
+    pub fn toggle_period(&mut self) {
+        self.period = match self.period {
+            Period::Week => Period::Month,
+            Period::Month => Period::Week,
+        };
+    }
+}
+
+/// The year of days shown on the heatmap screen, one column per week.
+#[derive(Debug, Clone, Copy)]
+pub struct HeatmapView {
+    pub cursor: NaiveDate,
+    /// Monday of the rightmost column.
+    pub last_week: NaiveDate,
+}
+
+impl HeatmapView {
+    pub fn new(cursor: NaiveDate) -> Self {
+        HeatmapView {
+            cursor,
+            last_week: week_start(cursor),
+        }
+    }
+
+    pub fn first_week(&self) -> NaiveDate {
+        self.last_week - Duration::weeks(HEATMAP_WEEKS - 1)
+    }
+
+    /// Moves the cursor, scrolling by a week when it leaves the grid.
+    pub fn move_cursor(&mut self, days: i64) {
+        self.cursor += Duration::days(days);
+        let week = week_start(self.cursor);
+        if week > self.last_week {
+            self.last_week = week;
+        } else if week < self.first_week() {
+            self.last_week = week + Duration::weeks(HEATMAP_WEEKS - 1);
+        }
+    }
+}
+
+/// Totals for one day of the report.
+pub struct DayTotal {
+    pub date: NaiveDate,
+    pub work: Duration,
+    pub rest: Duration,
+}
+
+pub struct Summary {
+    pub days: Vec<DayTotal>,
+    pub total_work: Duration,
+    pub total_break: Duration,
+    /// Work per day that had any work at all.
+    pub average_work: Duration,
+    /// Longest stretch of uninterrupted work.
+    pub longest_streak: Duration,
+    pub work_ratio: f64,
+}
+
+/// Work and break time of every day from `first` to `last`.
+pub fn day_totals(
+    sessions: &[Session],
+    timeline: &Timeline,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Vec<DayTotal> {
+    first
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .map(|date| {
+            let mut day = DayTotal {
+                date,
+                work: Duration::zero(),
+                rest: Duration::zero(),
+            };
+            for s in timeline
+                .day_indices(sessions, date)
+                .into_iter()
+                .map(|i| &sessions[i])
+            {
+                let on_day = s.clip_to_day(date).duration;
+                match s.session_type {
+                    SessionType::Work => day.work += on_day,
+                    SessionType::Break => day.rest += on_day,
+                    SessionType::Idle => {}
+                }
+            }
+            day
+        })
+        .collect()
+}
+
+pub fn summarize(sessions: &[Session], view: &ReportView) -> Summary {
+    let (first, last) = view.bounds();
+    let timeline = Timeline::new(sessions);
+    let days = day_totals(sessions, &timeline, first, last);
+
+    let mut longest_streak = Duration::zero();
+    let mut streak = Duration::zero();
+    let mut streak_end = None;
+    let (from, to) = (local_day_bounds(first).0, local_day_bounds(last).1);
+    for s in timeline
+        .overlapping(sessions, from, to)
+        .into_iter()
+        .map(|i| &sessions[i])
+    {
+        if s.session_type != SessionType::Work {
+            streak_end = None;
+            continue;
+        }
+        let continues = streak_end.is_some_and(|end| s.start_time - end < STREAK_GAP);
+        streak = if continues {
+            streak + s.duration()
+        } else {
+            s.duration()
+        };
+        streak_end = Some(s.end_time.unwrap_or(s.start_time + s.duration()));
+        longest_streak = longest_streak.max(streak);
+    }
+
+    let total_work = days.iter().fold(Duration::zero(), |sum, d| sum + d.work);
+    let total_break = days.iter().fold(Duration::zero(), |sum, d| sum + d.rest);
+    let worked_days = days.iter().filter(|d| d.work > Duration::zero()).count() as i32;
+    let average_work = if worked_days > 0 {
+        total_work / worked_days
+    } else {
+        Duration::zero()
+    };
+    let total = (total_work + total_break).num_seconds();
+    let work_ratio = if total > 0 {
+        total_work.num_seconds() as f64 / total as f64
+    } else {
+        0.0
+    };
+    Summary {
+        days,
+        total_work,
+        total_break,
+        average_work,
+        longest_streak,
+        work_ratio,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{date, logged};
+
+    #[test]
+    fn summaries_join_work_across_short_gaps_and_average_worked_days() {
+        use SessionType::*;
+        let sessions = vec![
+            // Tuesday: a 30 s gap keeps the streak going, a break ends it
+            logged(Work, "2024-05-14", "09:00:00", "10:00:00"),
+            logged(Work, "2024-05-14", "10:00:30", "11:00:00"),
+            logged(Break, "2024-05-14", "11:00:00", "11:15:00"),
+            logged(Work, "2024-05-14", "11:15:00", "12:00:00"),
+            // Wednesday and Friday have no work and don't count towards the average
+            logged(Idle, "2024-05-15", "09:00:00", "17:00:00"),
+            // Thursday: a whole minute apart are two streaks
+            logged(Work, "2024-05-16", "09:00:00", "10:00:00"),
+            logged(Work, "2024-05-16", "10:01:00", "11:41:00"),
+            logged(Break, "2024-05-17", "09:00:00", "09:30:00"),
+        ];
+        let summary = summarize(&sessions, &ReportView::new(date("2024-05-15")));
+
+        assert_eq!(summary.days.len(), 7);
+        assert_eq!(summary.days[0].date, date("2024-05-13"));
+        assert_eq!(summary.longest_streak, Duration::seconds(119 * 60 + 30));
+        assert_eq!(summary.total_work, Duration::seconds(324 * 60 + 30));
+        assert_eq!(summary.total_break, Duration::minutes(45));
+        assert_eq!(summary.average_work, Duration::seconds(162 * 60 + 15));
+        assert!((summary.work_ratio - 324.5 / 369.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn months_page_across_short_month_ends() {
+        let mut view = ReportView::new(date("2024-01-31"));
+        view.toggle_period();
+        view.page(1);
+        assert_eq!(view.bounds(), (date("2024-02-01"), date("2024-02-29")));
+        view.page(1);
+        assert_eq!(view.bounds(), (date("2024-03-01"), date("2024-03-31")));
+        view.page(-3);
+        assert_eq!(view.bounds(), (date("2023-12-01"), date("2023-12-31")));
+        assert_eq!(view.title(), "December 2023");
+    }
+
+    #[test]
+    fn weeks_page_across_month_and_year_ends() {
+        let mut view = ReportView::new(date("2024-12-31"));
+        assert_eq!(view.bounds(), (date("2024-12-30"), date("2025-01-05")));
+        assert_eq!(view.title(), "Week 1, 2025 (Dec 30 - Jan 05)");
+        view.page(-1);
+        assert_eq!(view.bounds(), (date("2024-12-23"), date("2024-12-29")));
+        view.page(6);
+        assert_eq!(view.bounds(), (date("2025-02-03"), date("2025-02-09")));
+    }
+
+    #[test]
+    fn the_heatmap_scrolls_a_week_when_the_cursor_leaves_it() {
+        // Wednesday, so the grid runs from Monday 52 weeks back to Sunday
+        let mut view = HeatmapView::new(date("2024-05-15"));
+        assert_eq!(view.last_week, date("2024-05-13"));
+        assert_eq!(view.first_week(), date("2023-05-15"));
+
+        // Moving inside the grid keeps it where it is
+        view.move_cursor(4);
+        assert_eq!(view.cursor, date("2024-05-19"));
+        assert_eq!(view.last_week, date("2024-05-13"));
+        view.move_cursor(-7 * 52 - 6);
+        assert_eq!(view.cursor, date("2023-05-15"));
+        assert_eq!(view.first_week(), date("2023-05-15"));
+
+        // One day past either side scrolls by a single week
+        view.move_cursor(-1);
+        assert_eq!(view.first_week(), date("2023-05-08"));
+        assert_eq!(view.last_week, date("2024-05-06"));
+        view.move_cursor(364);
+        assert_eq!(view.cursor, date("2024-05-12"));
+        assert_eq!(view.last_week, date("2024-05-06"));
+        view.move_cursor(1);
+        assert_eq!(view.last_week, date("2024-05-13"));
+        assert_eq!(view.first_week(), date("2023-05-15"));
+    }
+}
@@ -0,0 +1,369 @@
+use crate::animation::Animation;
+use crate::config::Config;
+use anyhow::{Context, Result, bail};
+use ratatui::{style::Color, text::Line};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Columns and rows inside the border of the pet panel `ui::ui` reserves.
+pub const PANEL_COLUMNS: usize = crate::ui::PET_PANEL_WIDTH as usize - 2;
+pub const PANEL_ROWS: usize = crate::ui::PET_PANEL_HEIGHT as usize - 2;
+
+/// How long a frame stays up unless the skin says otherwise: one tick.
+const DEFAULT_FRAME_MS: u64 = 200;
+
+/// A skin file as written on disk.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SkinFile {
+    name: Option<String>,
+    frame_ms: Option<u64>,
+    color: Option<String>,
+    #[serde(default)]
+    idle: Vec<FrameFile>,
+    #[serde(default)]
+    work: Vec<FrameFile>,
+    #[serde(default)]
+    focus: Vec<FrameFile>,
+    #[serde(default)]
+    overwork: Vec<FrameFile>,
+    #[serde(default, rename = "break")]
+    rest: Vec<FrameFile>,
+    #[serde(default)]
+    celebrate: Vec<FrameFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FrameFile {
+    art: String,
+    ms: Option<u64>,
+    color: Option<String>,
+}
+
+impl SkinFile {
+    fn set(&self, animation: Animation) -> &[FrameFile] {
+        match animation {
+            Animation::Idle => &self.idle,
+            Animation::Working => &self.work,
+            Animation::Focus => &self.focus,
+            Animation::Overwork => &self.overwork,
+            Animation::Break => &self.rest,
+            Animation::Celebrate => &self.celebrate,
+        }
+    }
+}
+
+pub struct Frame {
+    pub lines: Vec<String>,
+    pub ms: u64,
+    /// Overrides the colour of the session state.
+    pub color: Option<Color>,
+}
+
+/// Frame sets for every animation, either built in or loaded from a file.
+pub struct Skin {
+    /// Only the built-in art has room for the evolution stage overlays.
+    pub builtin: bool,
+    sets: HashMap<Animation, Vec<Frame>>,
+}
+
+impl Skin {
+    pub fn builtin() -> Self {
+        Skin {
+            builtin: true,
+            sets: Animation::ALL.map(|a| (a, builtin_set(a))).into(),
+        }
+    }
+
+    /// Loads a skin file, refusing it on any problem `validate-skin` would
+    /// report. States it leaves out keep the built-in frames.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = read(path)?;
+        let problems = check(&file);
+        if !problems.is_empty() {
+            bail!("skin {}: {}", path.display(), problems.join("; "));
+        }
+        let default_color = file.color.as_deref().map(parse_color).transpose()?;
+        let default_ms = file.frame_ms.unwrap_or(DEFAULT_FRAME_MS);
+        let mut sets = HashMap::new();
+        for animation in Animation::ALL {
+            let frames = file.set(animation);
+            let set = if frames.is_empty() {
+                builtin_set(animation)
+            } else {
+                frames
+                    .iter()
+                    .map(|frame| {
+                        Ok(Frame {
+                            lines: art_lines(&frame.art),
+                            ms: frame.ms.unwrap_or(default_ms),
+                            color: frame
+                                .color
+                                .as_deref()
+                                .map(parse_color)
+                                .transpose()?
+                                .or(default_color),
+                        })
+                    })
+                    .collect::<Result<_>>()?
+            };
+            sets.insert(animation, set);
+        }
+        Ok(Skin {
+            builtin: false,
+            sets,
+        })
+    }
+
+    /// The skin selected in the config, or the built-in one if none is
+    /// selected or it cannot be loaded. The error is returned for display.
+    pub fn from_config(config: &Config) -> (Self, Option<anyhow::Error>) {
+        let Some(name) = &config.skin else {
+            return (Skin::builtin(), None);
+        };
+        match skin_path(config, name).and_then(|path| Skin::load(&path)) {
+            Ok(skin) => (skin, None),
+            Err(err) => (Skin::builtin(), Some(err)),
+        }
+    }
+
+    pub fn frames(&self, animation: Animation) -> &[Frame] {
+        &self.sets[&animation]
+    }
+}
+
+fn builtin_set(animation: Animation) -> Vec<Frame> {
+    animation
+        .frames()
+        .iter()
+        .map(|lines| Frame {
+            lines: lines.iter().map(|l| l.to_string()).collect(),
+            ms: DEFAULT_FRAME_MS,
+            color: None,
+        })
+        .collect()
+}
+
+fn read(path: &Path) -> Result<SkinFile> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("reading skin {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("parsing skin {}", path.display()))
+}
+
+/// Splits the art into lines, dropping the line break that closes a
+/// multi-line string.
+fn art_lines(art: &str) -> Vec<String> {
+    art.strip_suffix('\n')
+        .unwrap_or(art)
+        .split('\n')
+        .map(|l| l.trim_end_matches('\r').to_string())
+        .collect()
+}
+
+fn parse_color(name: &str) -> Result<Color> {
+    name.parse()
+        .ok()
+        .with_context(|| format!("unknown color {name:?}"))
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map_or_else(String::new, |s| s.to_string_lossy().into_owned())
+}
+
+/// A skin is named by its file in the skins directory (without `.toml`),
+/// or given as a path.
+pub fn skin_path(config: &Config, name: &str) -> Result<PathBuf> {
+    let path = Path::new(name);
+    if path.extension().is_some() || path.components().count() > 1 {
+        return Ok(path.to_path_buf());
+    }
+    let dir = config
+        .resolve_skins_dir()
+        .context("no skins directory, give the skin as a path")?;
+    Ok(dir.join(format!("{name}.toml")))
+}
+
+/// `validate-skin` command: checks that a skin parses and that every frame
+/// fits the pet panel.
+pub fn validate(config: &Config, name: &str) -> Result<()> {
+    let path = skin_path(config, name)?;
+    let file = read(&path)?;
+    println!(
+        "Skin \"{}\" ({})",
+        file.name.clone().unwrap_or_else(|| file_stem(&path)),
+        path.display()
+    );
+    for animation in Animation::ALL {
+        match file.set(animation).len() {
+            0 => println!("  {}: built-in frames", animation.key()),
+            1 => println!("  {}: 1 frame", animation.key()),
+            n => println!("  {}: {n} frames", animation.key()),
+        }
+    }
+
+    let problems = check(&file);
+    if problems.is_empty() {
+        println!("OK");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("  ! {problem}");
+    }
+    bail!("{} problem(s) in {}", problems.len(), path.display())
+}
+
+/// Everything wrong with a skin: unknown colours, zero frame times and art
+/// that does not fit the pet panel.
+fn check(file: &SkinFile) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Some(color) = &file.color
+        && let Err(err) = parse_color(color)
+    {
+        problems.push(format!("color: {err}"));
+    }
+    if file.frame_ms == Some(0) {
+        problems.push("frame_ms must be at least 1".to_string());
+    }
+    for animation in Animation::ALL {
+        for (i, frame) in file.set(animation).iter().enumerate() {
+            let at = format!("{} frame {}", animation.key(), i + 1);
+            let lines = art_lines(&frame.art);
+            if lines.len() > PANEL_ROWS {
+                problems.push(format!(
+                    "{at}: {} lines, the pet panel fits {PANEL_ROWS}",
+                    lines.len()
+                ));
+            }
+            for (n, line) in lines.iter().enumerate() {
+                let width = Line::from(line.as_str()).width();
+                if width > PANEL_COLUMNS {
+                    problems.push(format!(
+                        "{at}, line {}: {width} columns, the pet panel fits {PANEL_COLUMNS}",
+                        n + 1
+                    ));
+                }
+                if line.contains('\t') {
+                    problems.push(format!("{at}, line {}: contains a tab", n + 1));
+                }
+            }
+            if frame.ms == Some(0) {
+                problems.push(format!("{at}: ms must be at least 1"));
+            }
+            if let Some(color) = &frame.color
+                && let Err(err) = parse_color(color)
+            {
+                problems.push(format!("{at}: {err}"));
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn write_skin(dir: &TempDir, toml: &str) -> PathBuf {
+        let path = dir.join("skin.toml");
+        fs::write(&path, toml).unwrap();
+        path
+    }
+
+    #[test]
+    fn skins_keep_the_built_in_frames_of_states_they_leave_out() {
+        let dir = TempDir::new();
+        let path = write_skin(
+            &dir,
+            r##"
+frame_ms = 500
+color = "yellow"
+
+[[work]]
+art = """
+o_o
+"""
+
+[[work]]
+art = "-_-"
+ms = 100
+color = "#ff0000"
+"##,
+        );
+        let skin = Skin::load(&path).unwrap();
+        assert!(!skin.builtin);
+
+        let work = skin.frames(Animation::Working);
+        assert_eq!(work.len(), 2);
+        assert_eq!(work[0].lines, ["o_o"]);
+        assert_eq!((work[0].ms, work[0].color), (500, Some(Color::Yellow)));
+        assert_eq!(
+            (work[1].ms, work[1].color),
+            (100, Some(Color::Rgb(255, 0, 0)))
+        );
+
+        let rest = skin.frames(Animation::Break);
+        assert_eq!(rest.len(), Animation::Break.frames().len());
+        assert_eq!((rest[0].ms, rest[0].color), (DEFAULT_FRAME_MS, None));
+    }
+
+    #[test]
+    fn skins_with_problems_validate_reports_are_not_loaded() {
+        let dir = TempDir::new();
+        let path = write_skin(
+            &dir,
+            &format!(
+                r#"
+frame_ms = 0
+
+[[idle]]
+art = "{}"
+
+[[idle]]
+art = "ok"
+ms = 0
+color = "nope"
+
+[[break]]
+art = "{}"
+"#,
+                "x".repeat(PANEL_COLUMNS + 1),
+                "a\\n".repeat(PANEL_ROWS + 1)
+            ),
+        );
+        let problems = check(&read(&path).unwrap());
+        assert_eq!(
+            problems,
+            [
+                "frame_ms must be at least 1".to_string(),
+                format!(
+                    "idle frame 1, line 1: {} columns, the pet panel fits {PANEL_COLUMNS}",
+                    PANEL_COLUMNS + 1
+                ),
+                "idle frame 2: ms must be at least 1".to_string(),
+                "idle frame 2: unknown color \"nope\"".to_string(),
+                format!(
+                    "break frame 1: {} lines, the pet panel fits {PANEL_ROWS}",
+                    PANEL_ROWS + 1
+                ),
+            ]
+        );
+
+        let Err(err) = Skin::load(&path) else {
+            panic!("loaded a skin with problems");
+        };
+        let err = err.to_string();
+        assert!(
+            problems.iter().all(|problem| err.contains(problem)),
+            "{err}"
+        );
+        let err = validate(&Config::default(), path.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().starts_with("5 problem(s)"), "{err}");
+    }
+}
@@ -0,0 +1,370 @@
+use crate::data::{LoadedLog, LogEvent, Session, Store, close_open_sessions, load_sessions};
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use std::path::{Path, PathBuf};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS sessions (
+        id       INTEGER PRIMARY KEY,
+        start_us INTEGER NOT NULL,
+        end_us   INTEGER,
+        data     TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS sessions_start ON sessions (start_us);
+    CREATE INDEX IF NOT EXISTS sessions_end ON sessions (end_us);
+";
+
+/// Sessions overlapping `[?1, ?2)`. A finished one ends after `?1` so it
+/// starts no earlier than the longest length before it, which bounds the
+/// index scan; running ones are found by their missing end.
+const RANGE_QUERY: &str = "
+    SELECT id, data, start_us FROM sessions
+    WHERE start_us < ?2 AND end_us > ?1
+      AND start_us >= ?1 - (SELECT IFNULL(MAX(length_us), 0) FROM sessions)
+    UNION ALL
+    SELECT id, data, start_us FROM sessions WHERE end_us IS NULL AND start_us < ?2
+    ORDER BY start_us, id
+";
+
+/// Changes to the schema since the first version, applied in order and
+/// counted in `PRAGMA user_version`.
+const MIGRATIONS: &[&str] = &["
+    ALTER TABLE sessions ADD COLUMN length_us INTEGER
+        GENERATED ALWAYS AS (end_us - start_us) VIRTUAL;
+    CREATE INDEX sessions_length ON sessions (length_us);
+"];
+
+/// SQLite backend. Start times and lengths are indexed, so a date-range query
+/// only reads sessions starting at most the longest session's length before
+/// the range; the session itself is stored as JSON so new fields need no
+/// migration.
+///
+/// `ids` holds the row id of every loaded session, in the same order as the
+/// app's list, so index-based events map onto rows.
+pub struct SqliteStore {
+    conn: Connection,
+    legacy_path: PathBuf,
+    ids: Vec<i64>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path, legacy_path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("opening database {}", path.display()))?;
+        conn.execute_batch(SCHEMA)?;
+        migrate(&conn).with_context(|| format!("upgrading database {}", path.display()))?;
+        Ok(SqliteStore {
+            conn,
+            legacy_path,
+            ids: Vec::new(),
+        })
+    }
+
+    /// Copies every session from a pretty-JSON log into the database.
+    fn import_json(&mut self, path: &Path) -> Result<usize> {
+        let sessions = load_sessions(path)?.sessions;
+        let tx = self.conn.transaction()?;
+        for session in &sessions {
+            insert(&tx, session)?;
+        }
+        tx.commit()?;
+        Ok(sessions.len())
+    }
+}
+
+fn migrate(conn: &Connection) -> Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        conn.execute_batch(&format!(
+            "BEGIN; {migration} PRAGMA user_version = {}; COMMIT;",
+            i + 1
+        ))?;
+    }
+    Ok(())
+}
+
+fn micros(t: DateTime<Utc>) -> i64 {
+    t.timestamp_micros()
+}
+
+fn id_at(ids: &[i64], index: usize) -> Result<i64> {
+    ids.get(index)
+        .copied()
+        .ok_or_else(|| anyhow!("event index {index} out of range ({})", ids.len()))
+}
+
+fn fetch(conn: &Connection, id: i64) -> Result<Session> {
+    let data: String = conn.query_row("SELECT data FROM sessions WHERE id = ?1", [id], |row| {
+        row.get(0)
+    })?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+fn insert(conn: &Connection, session: &Session) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO sessions (start_us, end_us, data) VALUES (?1, ?2, ?3)",
+        params![
+            micros(session.start_time),
+            session.end_time.map(micros),
+            serde_json::to_string(session)?
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn update(conn: &Connection, id: i64, session: &Session) -> Result<()> {
+    conn.execute(
+        "UPDATE sessions SET start_us = ?2, end_us = ?3, data = ?4 WHERE id = ?1",
+        params![
+            id,
+            micros(session.start_time),
+            session.end_time.map(micros),
+            serde_json::to_string(session)?
+        ],
+    )?;
+    Ok(())
+}
+
+fn rows(conn: &Connection, sql: &str, args: impl rusqlite::Params) -> Result<Vec<(i64, Session)>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(args, |row| Ok((row.get(0)?, row.get::<_, String>(1)?)))?;
+    rows.map(|row| {
+        let (id, data) = row?;
+        Ok((id, serde_json::from_str(&data)?))
+    })
+    .collect()
+}
+
+impl Store for SqliteStore {
+    fn load(&mut self) -> Result<LoadedLog> {
+        let empty = self
+            .conn
+            .query_row("SELECT id FROM sessions LIMIT 1", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .optional()?
+            .is_none();
+        if empty && self.legacy_path.exists() {
+            let legacy = self.legacy_path.clone();
+            self.import_json(&legacy)?;
+        }
+
+        let (ids, mut sessions): (Vec<i64>, Vec<Session>) = rows(
+            &self.conn,
+            "SELECT id, data FROM sessions ORDER BY start_us, id",
+            [],
+        )?
+        .into_iter()
+        .unzip();
+
+        let before: Vec<bool> = sessions.iter().map(|s| s.end_time.is_none()).collect();
+        close_open_sessions(&mut sessions);
+        let tx = self.conn.transaction()?;
+        for ((id, session), was_open) in ids.iter().zip(&sessions).zip(before) {
+            if was_open {
+                update(&tx, *id, session)?;
+            }
+        }
+        tx.commit()?;
+
+        self.ids = ids;
+        Ok(LoadedLog {
+            sessions,
+            recovered_from: None,
+        })
+    }
+
+    fn record(&mut self, _sessions: &[Session], events: &[LogEvent]) -> Result<()> {
+        let mut ids = self.ids.clone();
+        let tx = self.conn.transaction()?;
+        for event in events {
+            match event {
+                LogEvent::Started { session } => ids.push(insert(&tx, session)?),
+                LogEvent::Ended { index, end_time } => {
+                    let id = id_at(&ids, *index)?;
+                    let mut session = fetch(&tx, id)?;
+                    session.end_time = Some(*end_time);
+                    update(&tx, id, &session)?;
+                }
+                LogEvent::NoteChanged { index, note } => {
+                    let id = id_at(&ids, *index)?;
+                    let mut session = fetch(&tx, id)?;
+                    session.note = note.clone();
+                    update(&tx, id, &session)?;
+                }
+                LogEvent::ProjectChanged {
+                    index,
+                    project,
+                    tags,
+                } => {
+                    let id = id_at(&ids, *index)?;
+                    let mut session = fetch(&tx, id)?;
+                    session.project = project.clone();
+                    session.tags = tags.clone();
+                    update(&tx, id, &session)?;
+                }
+                LogEvent::Deleted { index } => {
+                    let id = id_at(&ids, *index)?;
+                    tx.execute("DELETE FROM sessions WHERE id = ?1", [id])?;
+                    ids.remove(*index);
+                }
+                LogEvent::Inserted { index, session } => {
+                    if *index > ids.len() {
+                        return Err(anyhow!("event index {index} out of range ({})", ids.len()));
+                    }
+                    ids.insert(*index, insert(&tx, session)?);
+                }
+                LogEvent::Replaced { index, session } => {
+                    update(&tx, id_at(&ids, *index)?, session)?;
+                }
+            }
+        }
+        tx.commit()?;
+        self.ids = ids;
+        Ok(())
+    }
+
+    fn sessions_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Session>> {
+        Ok(
+            rows(&self.conn, RANGE_QUERY, params![micros(from), micros(to)])?
+                .into_iter()
+                .map(|(_, session)| session)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SessionType;
+    use crate::testing::{TempDir, session, utc};
+
+    fn open(dir: &TempDir) -> SqliteStore {
+        SqliteStore::open(&dir.join("work_log.sqlite3"), dir.join("work_log.json")).unwrap()
+    }
+
+    fn work(start: &str, end: &str) -> Session {
+        session(SessionType::Work, start, Some(end))
+    }
+
+    #[test]
+    fn events_map_onto_rows() {
+        let dir = TempDir::new();
+        let mut store = open(&dir);
+        let mut sessions = store.load().unwrap().sessions;
+        let a = work("2024-05-05T09:00:00Z", "2024-05-05T10:00:00Z");
+        let b = work("2024-05-05T11:00:00Z", "2024-05-05T12:00:00Z");
+        let c = work("2024-05-05T13:00:00Z", "2024-05-05T14:00:00Z");
+        let events = [
+            LogEvent::Started { session: a },
+            LogEvent::Started { session: c },
+            LogEvent::Inserted {
+                index: 1,
+                session: b,
+            },
+            // Each of these must hit the row now at that index
+            LogEvent::NoteChanged {
+                index: 2,
+                note: "c".to_string(),
+            },
+            LogEvent::Deleted { index: 0 },
+            LogEvent::Replaced {
+                index: 0,
+                session: work("2024-05-05T11:30:00Z", "2024-05-05T12:00:00Z"),
+            },
+            LogEvent::NoteChanged {
+                index: 1,
+                note: "still c".to_string(),
+            },
+        ];
+        for event in &events {
+            event.apply(&mut sessions).unwrap();
+        }
+        store.record(&sessions, &events).unwrap();
+        assert_eq!(sessions[1].note, "still c");
+        assert_eq!(open(&dir).load().unwrap().sessions, sessions);
+
+        // The ids stay in step across separate calls too
+        let event = LogEvent::Deleted { index: 1 };
+        event.apply(&mut sessions).unwrap();
+        store.record(&sessions, &[event]).unwrap();
+        assert_eq!(open(&dir).load().unwrap().sessions, sessions);
+        assert!(
+            store
+                .record(&sessions, &[LogEvent::Deleted { index: 1 }])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn ranges_find_long_and_running_sessions() {
+        let dir = TempDir::new();
+        let mut store = open(&dir);
+        store.load().unwrap();
+        let long = work("2024-05-01T09:00:00Z", "2024-05-06T09:00:00Z");
+        let before = work("2024-05-04T09:00:00Z", "2024-05-04T10:00:00Z");
+        let inside = work("2024-05-05T09:00:00Z", "2024-05-05T10:00:00Z");
+        let running = session(SessionType::Idle, "2024-05-05T18:00:00Z", None);
+        let events: Vec<_> = [&long, &before, &inside, &running]
+            .into_iter()
+            .map(|session| LogEvent::Started {
+                session: session.clone(),
+            })
+            .collect();
+        store.record(&[], &events).unwrap();
+        let found = store
+            .sessions_between(utc("2024-05-05T00:00:00Z"), utc("2024-05-06T00:00:00Z"))
+            .unwrap();
+        assert_eq!(found, [long, inside, running]);
+    }
+
+    #[test]
+    fn ranges_search_the_start_index() {
+        let dir = TempDir::new();
+        let store = open(&dir);
+        let plan: Vec<String> = rows_of_plan(&store.conn);
+        assert!(
+            plan.iter()
+                .any(|step| step.contains("USING INDEX sessions_start (start_us>? AND start_us<?)")),
+            "{plan:?}"
+        );
+    }
+
+    fn rows_of_plan(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare(&format!("EXPLAIN QUERY PLAN {RANGE_QUERY}"))
+            .unwrap();
+        stmt.query_map(params![0, 1], |row| row.get::<_, String>(3))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn upgrades_databases_without_lengths() {
+        let dir = TempDir::new();
+        let path = dir.join("work_log.sqlite3");
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(SCHEMA).unwrap();
+        insert(&conn, &work("2024-05-05T09:00:00Z", "2024-05-05T10:00:00Z")).unwrap();
+        drop(conn);
+
+        let store = open(&dir);
+        let found = store
+            .sessions_between(utc("2024-05-05T00:00:00Z"), utc("2024-05-06T00:00:00Z"))
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            open(&dir)
+                .conn
+                .query_row("PRAGMA user_version", [], |row| row.get::<_, usize>(0))
+                .unwrap(),
+            MIGRATIONS.len()
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Helpers shared by the unit tests.
+
+use crate::calendar::{Calendar, Zone};
+use crate::data::{LogEvent, Session, SessionType, local_day_bounds};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use std::{
+    env, fs,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+pub fn utc(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+pub fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+pub fn utc_calendar() -> Calendar {
+    Calendar::new(Zone::parse("UTC").unwrap())
+}
+
+/// A session recorded in UTC, running if there is no `end`.
+pub fn session(kind: SessionType, start: &str, end: Option<&str>) -> Session {
+    Session {
+        start_time: utc(start),
+        end_time: end.map(utc),
+        session_type: kind,
+        note: String::new(),
+        project: None,
+        tags: Vec::new(),
+        zone: Some("UTC".to_string()),
+        uid: None,
+    }
+}
+
+/// A finished session on `day` between two `HH:MM:SS` times of the
+/// reporting calendar, so tests of day totals hold in any system timezone.
+pub fn logged(kind: SessionType, day: &str, from: &str, to: &str) -> Session {
+    let midnight = local_day_bounds(date(day)).0;
+    let at = |t: &str| midnight + (t.parse::<NaiveTime>().unwrap() - NaiveTime::MIN);
+    let mut session = Session::new(kind, at(from));
+    session.end_time = Some(at(to));
+    session
+}
+
+/// Checks that replaying `events` onto `before`, as a store does, gives `after`.
+pub fn replays_to(before: &[Session], events: &[LogEvent], after: &[Session]) {
+    let mut replayed = before.to_vec();
+    for event in events {
+        event.apply(&mut replayed).unwrap();
+    }
+    assert_eq!(replayed, after);
+}
+
+/// A fresh directory under the system temp dir, removed again on drop.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let path = env::temp_dir().join(format!("rust_pet_timer-test-{}-{n}", process::id()));
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
@@ -1,13 +1,22 @@
 use crate::App;
 use crate::InputMode;
+use crate::View;
 use crate::assets::*;
+use crate::calendar::calendar;
 use crate::data::{Session, SessionType};
-use chrono::Duration;
+use crate::editor::{Field, SessionForm, kind_name};
+use crate::pet::Stage;
+use crate::report::{self, HEATMAP_WEEKS, HeatmapView, Period, ReportView};
+use chrono::{Duration, NaiveDate};
 use ratatui::{
     prelude::*,
-    widgets::{Block, Borders, Cell, Gauge, Paragraph, Row, Table},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Cell, Clear, Gauge, Paragraph, Row, Table},
 };
 
+/// Size of the pet panel, border included.
+pub const PET_PANEL_WIDTH: u16 = 62;
+pub const PET_PANEL_HEIGHT: u16 = 14;
+
 pub fn ui(f: &mut Frame, app: &mut App) {
     let area = f.area();
 
@@ -26,10 +35,10 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(14), // Top Row (Pet + Dashboard)
-            Constraint::Length(3),  // Active Note Bar
-            Constraint::Min(10),    // History Table
-            Constraint::Length(3),  // Footer
+            Constraint::Length(PET_PANEL_HEIGHT), // Top Row (Pet + Dashboard)
+            Constraint::Length(3),                // Active Note Bar
+            Constraint::Min(10),                  // History Table
+            Constraint::Length(3),                // Footer
         ])
         .split(area);
 
@@ -37,29 +46,63 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     let top_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Length(62), // Fixed width for Pet (Left)
-            Constraint::Fill(1),    // Dashboard (Right - Fills remaining)
+            Constraint::Length(PET_PANEL_WIDTH), // Fixed width for Pet (Left)
+            Constraint::Fill(1),                 // Dashboard (Right - Fills remaining)
         ])
         .split(chunks[0]);
 
     // 1. PET COMPANION (LEFT)
     let active_session = app.get_active_session();
     let status_color = active_session.session_type.color();
+    let celebrating = app.celebrating();
 
-    let frame_lines: &[&str] = match active_session.session_type {
-        SessionType::Idle => &FRAME_DEAD,
-        _ => FRAMES_ACTIVE[app.animation_index],
+    let frames = app.skin.frames(app.animation);
+    let frame = &frames[app.animation_index % frames.len()];
+    let pet = app.pet_now();
+    let frame_lines = if app.skin.builtin {
+        stage_frame(&frame.lines, pet.stage())
+    } else {
+        frame.lines.clone()
     };
 
+    // Flash while celebrating a reached goal
+    let (pet_color, pet_title) = if celebrating {
+        let flash = if app.animation_index.is_multiple_of(2) {
+            Color::Magenta
+        } else {
+            Color::Yellow
+        };
+        (flash, " ur brain \u{2605} goal reached! \u{2605} ")
+    } else {
+        (frame.color.unwrap_or(status_color), " ur brain ")
+    };
     let pet_widget = Paragraph::new(frame_lines.join("\n"))
-        .style(Style::default().fg(status_color))
+        .style(Style::default().fg(pet_color))
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL).title(" ur brain "));
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(pet_title)
+                .title_bottom(format!(
+                    " Lv{} {} | {} | hunger {} energy {} joy {} ",
+                    pet.level(),
+                    pet.stage().name(),
+                    pet.mood(),
+                    pet.hunger,
+                    pet.energy,
+                    pet.happiness
+                )),
+        );
 
     f.render_widget(pet_widget, top_chunks[0]);
 
     // 2. DASHBOARD (RIGHT)
-    let db_block = Block::default().borders(Borders::ALL);
+    let mut db_block = Block::default().borders(Borders::ALL);
+    if let Some(warning) = app.store.warning() {
+        db_block = db_block.title_bottom(
+            Line::from(format!(" ! {} ", warning)).style(Style::default().fg(Color::Yellow)),
+        );
+    }
     let db_inner = db_block.inner(top_chunks[1]);
     f.render_widget(db_block, top_chunks[1]);
 
@@ -76,7 +119,11 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         .split(db_inner);
 
     // A. Status Label
-    let status_label = Paragraph::new(active_session.session_type.label())
+    let label = match &app.pomodoro {
+        Some(pomodoro) => pomodoro.label(active_session.session_type),
+        None => active_session.session_type.label().to_string(),
+    };
+    let status_label = Paragraph::new(label)
         .style(
             Style::default()
                 .fg(status_color)
@@ -86,8 +133,11 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     f.render_widget(status_label, db_layout[0]);
 
     // B. Numeric Timer
-    let duration = active_session.duration();
-    let time_str = format_duration_str(duration);
+    // Pomodoro segments count down, free-running sessions count up
+    let time_str = match app.pomodoro_remaining() {
+        Some(remaining) => format!("{} left", format_duration_str(remaining)),
+        None => format_duration_str(active_session.duration()),
+    };
     let timer_widget = Paragraph::new(time_str)
         .style(
             Style::default()
@@ -109,9 +159,25 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         0.0
     };
 
+    // Goal progress sits next to the ratio when goals are configured
+    let halves = |area: Rect| {
+        let constraints = if app.goals.is_set() {
+            vec![Constraint::Fill(1), Constraint::Fill(1)]
+        } else {
+            vec![Constraint::Fill(1)]
+        };
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .spacing(2)
+            .split(area)
+    };
+    let labels = halves(db_layout[3]);
+    let gauges = halves(db_layout[4]);
+
     f.render_widget(
         Paragraph::new("Today's Work Ratio:").alignment(Alignment::Center),
-        db_layout[3],
+        labels[0],
     );
 
     let gauge = Gauge::default()
@@ -120,34 +186,194 @@ pub fn ui(f: &mut Frame, app: &mut App) {
         .ratio(ratio)
         .label(format!("{:.0}% Work", ratio * 100.0))
         .use_unicode(true);
-    f.render_widget(gauge, db_layout[4]);
+    f.render_widget(gauge, gauges[0]);
+
+    if app.goals.is_set() {
+        f.render_widget(
+            Paragraph::new("Daily Goal:").alignment(Alignment::Center),
+            labels[1],
+        );
+        let goal_gauge = match app.goals.for_date(app.selected_date) {
+            Some(goal) => {
+                let progress = (work_secs / goal.num_seconds() as f64).min(1.0);
+                Gauge::default()
+                    .gauge_style(Style::default().fg(Color::Magenta).bg(Color::DarkGray))
+                    .ratio(progress)
+                    .label(format!(
+                        "{} / {}",
+                        format_duration_str(work_dur),
+                        format_duration_str(goal)
+                    ))
+            }
+            None => Gauge::default()
+                .gauge_style(Style::default().fg(Color::DarkGray).bg(Color::DarkGray))
+                .ratio(0.0)
+                .label("Day off"),
+        };
+        f.render_widget(goal_gauge.use_unicode(true), gauges[1]);
+
+        let days = if app.goal_streak == 1 { "day" } else { "days" };
+        let streak = Paragraph::new(format!("Goal streak: {} {}", app.goal_streak, days))
+            .style(Style::default().fg(Color::Magenta))
+            .alignment(Alignment::Center);
+        f.render_widget(streak, db_layout[5]);
+    }
 
     // --- MIDDLE: NOTE BAR ---
-    let note_text = if !active_session.note.is_empty() {
-        format!(" NOTE: {}", active_session.note)
+    let project = active_session.project_label();
+    let project = if project.is_empty() {
+        String::new()
     } else {
-        " (No note for current session)".to_string()
+        format!(" [{}]", project)
+    };
+    let (note_text, note_color) = if let Some(notice) = &app.notice {
+        (format!(" ! {}", notice), Color::Red)
+    } else if !active_session.note.is_empty() {
+        (
+            format!("{} NOTE: {}", project, active_session.note),
+            Color::Cyan,
+        )
+    } else {
+        (
+            format!("{} (No note for current session)", project),
+            Color::Cyan,
+        )
     };
 
     let note_widget = Paragraph::new(note_text)
-        .style(Style::default().fg(Color::Cyan))
+        .style(Style::default().fg(note_color))
         .block(Block::default().borders(Borders::ALL));
 
     f.render_widget(note_widget, chunks[1]);
 
-    // --- BOTTOM: HISTORY ---
-    render_history_table(f, app, chunks[2]);
+    // --- BOTTOM: HISTORY OR REPORT ---
+    match app.view {
+        View::History => render_history_table(f, app, chunks[2]),
+        View::Report(view) => render_report(f, app, &view, chunks[2]),
+        View::Heatmap(view) => {
+            app.update_heatmap(&view);
+            render_heatmap(f, app, &view, chunks[2]);
+        }
+    }
 
     // --- FOOTER ---
     render_footer(f, app, chunks[3]);
+
+    if let Some(popup) = &app.reminder {
+        render_popup(
+            f,
+            " Reminder ",
+            &popup.message,
+            "Enter:OK | 'z':Snooze | SPC:Toggle work/break",
+        );
+    }
+    if app.show_keys {
+        render_key_help(f);
+    }
+    if let Some(away) = &app.away {
+        let minutes = (away.until - away.since).num_minutes();
+        render_popup(
+            f,
+            " Welcome back ",
+            &format!(
+                "You were away {minutes} min \u{2014} keep as work, convert to break, or discard?"
+            ),
+            "'k'/Enter:Keep as work | 'b':Convert to break | 'd':Discard",
+        );
+    }
+}
+
+/// Draws a message box over the middle of the screen.
+fn render_popup(f: &mut Frame, title: &str, message: &str, keys: &str) {
+    let width = (message.chars().count().max(keys.chars().count()) as u16 + 4).min(f.area().width);
+    let area = f.area().inner(Margin::new(
+        f.area().width.saturating_sub(width) / 2,
+        f.area().height.saturating_sub(6) / 2,
+    ));
+    let text = vec![
+        Line::from(message),
+        Line::from(""),
+        Line::from(keys).style(Style::default().fg(Color::DarkGray)),
+    ];
+    let popup = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Yellow))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// Every key of the main screen, shown by `?`.
+const KEY_HELP: &[(&str, &str)] = &[
+    ("SPC", "Toggle work/break"),
+    ("s", "Stop (idle)"),
+    ("p", "Pomodoro mode"),
+    ("n / Enter", "Note of the current / selected session"),
+    ("t", "Project and #tags"),
+    ("a / m", "Add a session / change its times"),
+    ("x / j", "Split / join with the next"),
+    ("d", "Delete the selected session"),
+    ("e / i", "Export the day as CSV / ICS"),
+    ("r / h", "Report / heatmap"),
+    (
+        "\u{2191}\u{2193} / \u{2190}\u{2192}",
+        "Select a session / change day",
+    ),
+    ("Esc", "Clear the selection"),
+    ("q", "Quit"),
+];
+
+fn render_key_help(f: &mut Frame) {
+    let lines: Vec<Line> = KEY_HELP
+        .iter()
+        .map(|(key, action)| Line::from(format!("{key:>9}  {action}")))
+        .collect();
+    let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4;
+    let height = lines.len() as u16 + 2;
+    let area = f.area().inner(Margin::new(
+        f.area().width.saturating_sub(width) / 2,
+        f.area().height.saturating_sub(height) / 2,
+    ));
+    let help = Paragraph::new(lines)
+        .style(Style::default().fg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Keys ")
+                .title_bottom(" any key to close "),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(help, area);
+}
+
+/// Draws the evolution stage of the pet into an animation frame.
+fn stage_frame(frame: &[String], stage: Stage) -> Vec<String> {
+    let patch = match stage {
+        Stage::Egg => STAGE_EGG,
+        Stage::Spark => STAGE_SPARK,
+        Stage::Heart => &[],
+        Stage::Crowned => STAGE_CROWNED,
+    };
+    frame
+        .iter()
+        .enumerate()
+        .map(|(i, line)| match patch.iter().find(|(n, _)| *n == i) {
+            Some((_, art)) if line.len() >= PET_COLUMNS.end => format!(
+                "{}{}{}",
+                &line[..PET_COLUMNS.start],
+                art,
+                &line[PET_COLUMNS.end..]
+            ),
+            _ => line.clone(),
+        })
+        .collect()
 }
 
 fn render_history_table(f: &mut Frame, app: &mut App, area: Rect) {
     let sessions_for_date: Vec<&Session> = app
-        .sessions
-        .iter()
-        .filter(|s| s.start_time_local().date_naive() == app.selected_date)
-        .rev()
+        .selected_day_indices()
+        .into_iter()
+        .map(|i| &app.sessions[i])
         .collect();
 
     let (total_work, total_break) = app.cached_today_stats;
@@ -155,28 +381,69 @@ fn render_history_table(f: &mut Frame, app: &mut App, area: Rect) {
     let rows: Vec<Row> = sessions_for_date
         .iter()
         .map(|item| {
-            let end_str = item
-                .end_time_local()
-                .map_or("Active".to_string(), |t| t.format("%H:%M:%S").to_string());
+            // Arrows mark sessions carried over from or into another day;
+            // the time column only counts this day's part
+            let clip = item.clip_to_day(app.selected_date);
+            let start_str = item.start_time_local().format("%H:%M").to_string();
+            let start_str = if clip.from_previous_day {
+                format!("\u{25c2} {start_str}")
+            } else {
+                start_str
+            };
+            let end_str = match item.end_time_local() {
+                Some(t) if clip.into_next_day => format!("{} \u{25b8}", t.format("%H:%M:%S")),
+                Some(t) => t.format("%H:%M:%S").to_string(),
+                None => "Active".to_string(),
+            };
 
             let cells = vec![
-                Cell::from(item.start_time_local().format("%H:%M").to_string()),
+                Cell::from(start_str),
                 Cell::from(end_str),
                 Cell::from(item.session_type.label())
                     .style(Style::default().fg(item.session_type.color())),
-                Cell::from(format_duration_str(item.duration())),
+                Cell::from(format_duration_str(clip.duration)),
+                Cell::from(item.project_label()),
                 Cell::from(item.note.clone()),
             ];
             Row::new(cells).height(1)
         })
         .collect();
 
-    let date_header = format!(" Log: {} ", app.selected_date.format("%Y-%m-%d"));
+    let goal_status = match app.goal_met(app.selected_date) {
+        Some(true) => " | Goal met \u{2713}",
+        Some(false) if app.selected_date == calendar().today() => " | Goal open",
+        Some(false) => " | Goal missed",
+        None => "",
+    };
+    let date_header = format!(
+        " Log: {}{} ",
+        app.selected_date.format("%Y-%m-%d"),
+        goal_status
+    );
     let stats_header = format!(
         " Daily Total | Work: {} | Break: {} ",
         format_duration_str(total_work),
         format_duration_str(total_break)
     );
+    let project_header = project_totals(&sessions_for_date, app.selected_date)
+        .iter()
+        .map(|(project, total)| {
+            format!(
+                "{}: {}",
+                project.unwrap_or("(none)"),
+                format_duration_str(*total)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(date_header)
+        .title_bottom(stats_header);
+    if !project_header.is_empty() {
+        block = block.title_bottom(Line::from(format!(" {} ", project_header)).right_aligned());
+    }
 
     let table = Table::new(
         rows,
@@ -185,28 +452,230 @@ fn render_history_table(f: &mut Frame, app: &mut App, area: Rect) {
             Constraint::Length(10),
             Constraint::Length(12),
             Constraint::Length(10),
+            Constraint::Length(18),
             Constraint::Min(10),
         ],
     )
     .header(
-        Row::new(vec!["Start", "End", "Type", "Time", "Note"])
+        Row::new(vec!["Start", "End", "Type", "Time", "Project", "Note"])
             .style(Style::default().fg(Color::Cyan)),
     )
     .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(date_header)
-            .title_bottom(stats_header),
-    );
+    .block(block);
 
     f.render_stateful_widget(table, area, &mut app.table_state);
 }
 
+fn render_report(f: &mut Frame, app: &App, view: &ReportView, area: Rect) {
+    let summary = report::summarize(&app.sessions, view);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Report: {} ", view.title()));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Fill(1), Constraint::Length(30)])
+        .split(inner);
+
+    // Work hours per day, one decimal so month bars stay narrow
+    let bars: Vec<Bar> = summary
+        .days
+        .iter()
+        .map(|day| {
+            let minutes = day.work.num_minutes().max(0) as u64;
+            let label = match view.period {
+                Period::Week => day.date.format("%a %d").to_string(),
+                Period::Month => day.date.format("%-d").to_string(),
+            };
+            let style = if day.date == app.selected_date {
+                Style::default().fg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::Green)
+            };
+            Bar::default()
+                .value(minutes)
+                .label(Line::from(label))
+                .text_value(format!("{:.1}", minutes as f64 / 60.0))
+                .style(style)
+        })
+        .collect();
+    let count = bars.len().max(1) as u16;
+    let bar_width = (columns[0].width / count).saturating_sub(1).max(1);
+    let chart = BarChart::default()
+        .block(Block::default().title(" Work hours per day "))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .value_style(Style::default().fg(Color::Black).bg(Color::Green));
+    f.render_widget(chart, columns[0]);
+
+    let stats = vec![
+        Line::from(format!(
+            "Total work:   {}",
+            format_duration_str(summary.total_work)
+        )),
+        Line::from(format!(
+            "Total break:  {}",
+            format_duration_str(summary.total_break)
+        )),
+        Line::from(format!(
+            "Avg work/day: {}",
+            format_duration_str(summary.average_work)
+        )),
+        Line::from(format!(
+            "Best focus:   {}",
+            format_duration_str(summary.longest_streak)
+        )),
+        Line::from(format!("Work ratio:   {:.0}%", summary.work_ratio * 100.0)),
+    ];
+    let stats_widget =
+        Paragraph::new(stats).block(Block::default().borders(Borders::LEFT).title(" Summary "));
+    f.render_widget(stats_widget, columns[1]);
+}
+
+/// Glyph and colour of a heatmap cell, darker the more work was done.
+fn heat_cell(work: Duration) -> (&'static str, Color) {
+    match work.num_hours() {
+        _ if work <= Duration::zero() => ("\u{b7}", Color::DarkGray),
+        0..2 => ("\u{2591}", Color::Green),
+        2..4 => ("\u{2592}", Color::Green),
+        4..6 => ("\u{2593}", Color::Green),
+        _ => ("\u{2588}", Color::Green),
+    }
+}
+
+fn render_heatmap(f: &mut Frame, app: &App, view: &HeatmapView, area: Rect) {
+    let first = view.first_week();
+    let last = view.last_week + Duration::days(6);
+    let today = calendar().today();
+    let days = app
+        .heatmap_days
+        .as_ref()
+        .map_or(&[][..], |(_, _, days)| days);
+    let work_on = |date: chrono::NaiveDate| days[(date - first).num_days() as usize].work;
+
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        " Activity: {} - {} ",
+        first.format("%b %Y"),
+        last.format("%b %Y")
+    ));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    // Two columns per week when there is room, one otherwise
+    const LABEL_WIDTH: usize = 4;
+    let cell_width = if inner.width as usize >= LABEL_WIDTH + HEATMAP_WEEKS as usize * 2 {
+        2
+    } else {
+        1
+    };
+    let weeks = (0..HEATMAP_WEEKS).map(|w| first + Duration::weeks(w));
+
+    // Month names above the first week that starts in them
+    let mut months = vec![' '; LABEL_WIDTH + HEATMAP_WEEKS as usize * cell_width + 3];
+    let mut free_from = 0;
+    let mut previous_month = None;
+    for (w, monday) in weeks.clone().enumerate() {
+        let month = monday.format("%b").to_string();
+        let pos = LABEL_WIDTH + w * cell_width;
+        if previous_month.as_ref() != Some(&month) && pos >= free_from {
+            months.splice(pos..pos + 3, month.chars());
+            free_from = pos + 4;
+        }
+        previous_month = Some(month);
+    }
+    let mut lines = vec![Line::from(months.into_iter().collect::<String>())];
+
+    for (d, label) in ["Mon", "", "Wed", "", "Fri", "", ""].iter().enumerate() {
+        let mut spans = vec![Span::raw(format!("{:<LABEL_WIDTH$}", label))];
+        for monday in weeks.clone() {
+            let date = monday + Duration::days(d as i64);
+            let (glyph, color) = if date > today {
+                (" ", Color::Reset)
+            } else {
+                heat_cell(work_on(date))
+            };
+            let mut style = Style::default().fg(color);
+            if date == view.cursor {
+                style = style.fg(Color::Cyan).add_modifier(Modifier::REVERSED);
+            }
+            spans.push(Span::styled(glyph, style));
+            if cell_width == 2 {
+                spans.push(Span::raw(" "));
+            }
+        }
+        lines.push(Line::from(spans));
+    }
+
+    let total = days.iter().fold(Duration::zero(), |sum, d| sum + d.work);
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled(
+            format!(
+                "{}: {} work",
+                view.cursor.format("%a %Y-%m-%d"),
+                format_duration_str(if view.cursor > last || view.cursor < first {
+                    Duration::zero()
+                } else {
+                    work_on(view.cursor)
+                })
+            ),
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw(format!(
+            "  |  Shown: {}  |  Less \u{b7} \u{2591} \u{2592} \u{2593} \u{2588} More",
+            format_duration_str(total)
+        )),
+    ]));
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+/// Work time per project, largest first. Sessions without a project are
+/// grouped under `None`; nothing is returned if no project was used.
+fn project_totals<'a>(
+    sessions: &[&'a Session],
+    date: NaiveDate,
+) -> Vec<(Option<&'a str>, Duration)> {
+    let mut totals: Vec<(Option<&str>, Duration)> = Vec::new();
+    for s in sessions
+        .iter()
+        .filter(|s| s.session_type == SessionType::Work)
+    {
+        let project = s.project.as_deref();
+        let on_day = s.clip_to_day(date).duration;
+        match totals.iter_mut().find(|(p, _)| *p == project) {
+            Some((_, total)) => *total += on_day,
+            None => totals.push((project, on_day)),
+        }
+    }
+    if totals.iter().all(|(p, _)| p.is_none()) {
+        return Vec::new();
+    }
+    totals.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+    totals
+}
+
 fn render_footer(f: &mut Frame, app: &mut App, area: Rect) {
     match app.input_mode {
+        InputMode::Normal if !matches!(app.view, View::History) => {
+            let help_text = match app.view {
+                View::Heatmap(_) => {
+                    "\u{2190}\u{2192}:Week | \u{2191}\u{2193}:Day | Enter:Open day | 'h'/Esc:Back to day"
+                }
+                _ => "\u{2190}\u{2192}:Page | Tab:Week/Month | 'r'/Esc:Back to day",
+            };
+            let help = Paragraph::new(help_text)
+                .style(Style::default().fg(Color::DarkGray))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::TOP));
+            f.render_widget(help, area);
+        }
         InputMode::Normal => {
-            let help_text = "SPC:Toggle | 's':Stop | 'n':Note | 'd':Del | \u{2191}\u{2193}:Nav | Enter:Edit | Esc:Clear";
+            let help_text =
+                "SPC:Toggle | 's':Stop | 'p':Pomodoro | 'n':Note | 't':Project | '?':All keys";
             let help = Paragraph::new(help_text)
                 .style(Style::default().fg(Color::DarkGray))
                 .alignment(Alignment::Center)
@@ -224,10 +693,80 @@ fn render_footer(f: &mut Frame, app: &mut App, area: Rect) {
                 .block(Block::default().borders(Borders::ALL).title(title));
             f.render_widget(input, area);
         }
+        InputMode::PickingProject => {
+            let suggestions = app.project_suggestions();
+            let title = if suggestions.is_empty() {
+                " Project #tags ".to_string()
+            } else {
+                format!(
+                    " Project #tags | Tab: {} ",
+                    suggestions
+                        .iter()
+                        .take(5)
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            let input = Paragraph::new(format!("> {}", app.input_buffer))
+                .style(Style::default().fg(Color::Yellow))
+                .block(Block::default().borders(Borders::ALL).title(title));
+            f.render_widget(input, area);
+        }
+        InputMode::SplittingSession => {
+            let input = Paragraph::new(format!("> {}", app.input_buffer))
+                .style(Style::default().fg(Color::Yellow))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(" Split at HH:MM | Enter:Split | Esc:Cancel "),
+                );
+            f.render_widget(input, area);
+        }
+        InputMode::EditingSession => {
+            if let Some(form) = &app.session_form {
+                render_session_form(f, form, area);
+            }
+        }
     }
 }
 
-fn format_duration_str(d: Duration) -> String {
+fn render_session_form(f: &mut Frame, form: &SessionForm, area: Rect) {
+    let field = |label: &str, value: String, active: bool| {
+        let style = if active {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::Yellow)
+        };
+        vec![
+            Span::raw(format!("{label}: ")),
+            Span::styled(format!(" {value:<8} "), style),
+            Span::raw("   "),
+        ]
+    };
+    let end = if form.running {
+        "running".to_string()
+    } else {
+        form.end.clone()
+    };
+    let mut spans = field("Start", form.start.clone(), form.field == Field::Start);
+    spans.extend(field("End", end, form.field == Field::End));
+    spans.extend(field(
+        "Type",
+        kind_name(form.kind).to_string(),
+        form.field == Field::Type,
+    ));
+    let title = format!(
+        " {} session on {} | Tab:Field | SPC:Type | Enter:Save | Esc:Cancel ",
+        if form.index.is_some() { "Edit" } else { "Add" },
+        form.date.format("%Y-%m-%d")
+    );
+    let input = Paragraph::new(Line::from(spans))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(input, area);
+}
+
+pub fn format_duration_str(d: Duration) -> String {
     let total_seconds = d.num_seconds();
     let h = total_seconds / 3600;
     let m = (total_seconds % 3600) / 60;